@@ -0,0 +1,97 @@
+use std::{env, io};
+
+use anyhow::{Context, Result};
+use clap_complete::{env::Shells, CompletionCandidate};
+
+use crate::{get::expand_home, settings, Hub};
+
+pub const SHELLS: [&str; 4] = ["bash", "zsh", "fish", "elvish"];
+
+// the shell runs 'DOTHUB_COMPLETE=<shell> dothub -- <words>' for every completion
+pub const VAR: &str = "DOTHUB_COMPLETE";
+
+// 'dothub completions', the script that hooks dothub into the shell
+pub fn completions(shell: &str) -> Result<()> {
+    let shells = Shells::builtins();
+    let completer = shells
+        .completer(shell)
+        .with_context(|| format!("Unknown shell '{}'.", shell))?;
+
+    completer
+        .write_registration(VAR, "dothub", "dothub", "dothub", &mut io::stdout())
+        .context("Couldn't write the completions.")
+}
+
+// the hub of '--hub', $DOTHUB_HOME or the settings, like the command being completed will use
+fn hub() -> Option<Hub> {
+    let _ = settings::load();
+
+    let hub = hub_arg().map(|hub| expand_home(&hub));
+
+    Hub::load(&settings::hub_path(hub.as_ref()).ok()?).ok()
+}
+
+// '--hub' of the line being completed, it isn't parsed yet, the shell passes its words after '--'
+fn hub_arg() -> Option<String> {
+    let words: Vec<String> = env::args().skip_while(|arg| arg != "--").skip(1).collect();
+
+    // the last one is the word being completed
+    let words = &words[..words.len().saturating_sub(1)];
+
+    words
+        .iter()
+        .enumerate()
+        .rev()
+        .find_map(|(i, word)| match word.strip_prefix("--hub=") {
+            Some(hub) => Some(hub.to_owned()),
+            None if word == "--hub" => words.get(i + 1).cloned(),
+            None => None,
+        })
+}
+
+// every 'DotFolder/Dot', the one that is set says so
+pub fn dots() -> Vec<CompletionCandidate> {
+    let hub = match hub() {
+        Some(hub) => hub,
+        None => return vec![],
+    };
+
+    hub.dot_folders
+        .iter()
+        .flat_map(|df| {
+            df.dots.iter().map(|d| {
+                let candidate = CompletionCandidate::new(format!("{}/{}", df.name, d.name));
+
+                match hub.is_active(df, d) {
+                    true => candidate.help(Some("set".into())),
+                    false => candidate,
+                }
+            })
+        })
+        .collect()
+}
+
+pub fn dotfolders() -> Vec<CompletionCandidate> {
+    hub().map_or(vec![], |hub| {
+        hub.dot_folders
+            .iter()
+            .map(|df| CompletionCandidate::new(&df.name))
+            .collect()
+    })
+}
+
+// for the commands that take a DotFolder or a Dot
+pub fn locations() -> Vec<CompletionCandidate> {
+    let mut candidates = dotfolders();
+    candidates.extend(dots());
+    candidates
+}
+
+pub fn profiles() -> Vec<CompletionCandidate> {
+    hub().map_or(vec![], |hub| {
+        hub.profiles
+            .iter()
+            .map(|profile| CompletionCandidate::new(&profile.name))
+            .collect()
+    })
+}
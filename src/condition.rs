@@ -0,0 +1,120 @@
+use std::{
+    collections::HashMap,
+    env,
+    ffi::CStr,
+    fs,
+    sync::{Mutex, OnceLock},
+};
+
+use schemars::JsonSchema;
+use serde_derive::Deserialize;
+
+use crate::settings;
+
+// the result of every 'command' condition, configs are resolved many times per run
+static COMMANDS: OnceLock<Mutex<HashMap<String, bool>>> = OnceLock::new();
+
+// [when], every condition that is given has to hold
+#[derive(Debug, Deserialize, JsonSchema, Clone, PartialEq, Default)]
+#[serde(deny_unknown_fields)]
+pub struct When {
+    hostname: Option<String>,
+    username: Option<String>,
+    // 'ID' or one of 'ID_LIKE' from /etc/os-release, or 'linux', 'macos', ...
+    os: Option<String>,
+    // 'NAME' has to be set, 'NAME=value' has to have that value
+    env: Option<String>,
+    // has to exit successfully
+    command: Option<String>,
+}
+
+impl When {
+    pub fn holds(&self) -> bool {
+        if let Some(name) = &self.hostname {
+            if hostname().as_ref() != Some(name) {
+                return false;
+            }
+        }
+
+        if let Some(name) = &self.username {
+            if username().as_ref() != Some(name) {
+                return false;
+            }
+        }
+
+        if let Some(os) = &self.os {
+            if !os_ids().contains(os) {
+                return false;
+            }
+        }
+
+        if let Some(var) = &self.env {
+            let set = match var.split_once('=') {
+                Some((name, value)) => env::var(name).is_ok_and(|v| v == value),
+                None => env::var_os(var).is_some(),
+            };
+
+            if !set {
+                return false;
+            }
+        }
+
+        match &self.command {
+            Some(command) => command_holds(command),
+            None => true,
+        }
+    }
+}
+
+fn command_holds(command: &str) -> bool {
+    let mut commands = COMMANDS
+        .get_or_init(Default::default)
+        .lock()
+        .unwrap_or_else(|e| e.into_inner());
+
+    *commands.entry(command.to_owned()).or_insert_with(|| {
+        settings::shell_command(command)
+            .output()
+            .is_ok_and(|output| output.status.success())
+    })
+}
+
+// the daemon runs the commands again once the hub changed
+pub fn forget_commands() {
+    if let Some(commands) = COMMANDS.get() {
+        commands.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+}
+
+fn hostname() -> Option<String> {
+    let mut buffer = [0 as libc::c_char; 256];
+
+    if unsafe { libc::gethostname(buffer.as_mut_ptr(), buffer.len()) } != 0 {
+        return None;
+    }
+
+    let name = unsafe { CStr::from_ptr(buffer.as_ptr()) };
+
+    Some(name.to_string_lossy().into_owned())
+}
+
+fn username() -> Option<String> {
+    env::var("USER").or_else(|_| env::var("LOGNAME")).ok()
+}
+
+fn os_ids() -> Vec<String> {
+    let mut ids = vec![env::consts::OS.to_owned()];
+
+    let os_release = fs::read_to_string("/etc/os-release")
+        .or_else(|_| fs::read_to_string("/usr/lib/os-release"))
+        .unwrap_or_default();
+
+    for line in os_release.lines() {
+        if let Some(("ID" | "ID_LIKE", value)) = line.split_once('=') {
+            let value = value.trim_matches(|c| c == '"' || c == '\'');
+            ids.extend(value.split_whitespace().map(str::to_owned));
+        }
+    }
+
+    ids
+}
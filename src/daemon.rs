@@ -0,0 +1,447 @@
+use std::{
+    collections::BTreeMap,
+    env, fs,
+    hash::{DefaultHasher, Hash, Hasher},
+    io::{BufRead, BufReader, ErrorKind, Read, Write},
+    os::unix::net::{UnixListener, UnixStream},
+    path::{Path, PathBuf},
+    process::{Child, ExitStatus},
+    sync::mpsc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Context, Result};
+use clap::ArgMatches;
+use notify::{Config, PollWatcher, RecursiveMode, Watcher};
+
+use crate::{
+    condition, dot_kill, dot_reload, get_active_config,
+    output::{self, Format},
+    schedule::Scheduler,
+    settings, Hub,
+};
+
+// a started program that crashes is restarted, but not forever
+const MAX_RESTARTS: u32 = 3;
+const STABLE_AFTER: Duration = Duration::from_secs(30);
+
+// every hub has its own daemon, the one in ~/.dothub keeps the plain name
+pub fn socket_path(hub_path: &Path) -> PathBuf {
+    let mut name = match env::var_os("XDG_RUNTIME_DIR") {
+        Some(_) => "dothub".to_owned(),
+        None => format!("dothub-{}", env::var("USER").unwrap_or_default()),
+    };
+
+    if settings::default_hub_path().ok().as_deref() != Some(hub_path) {
+        let mut hasher = DefaultHasher::new();
+        hub_path.hash(&mut hasher);
+        name += &format!("-{:016x}", hasher.finish());
+    }
+
+    match env::var_os("XDG_RUNTIME_DIR") {
+        Some(dir) => PathBuf::from(dir).join(name + ".sock"),
+        None => env::temp_dir().join(name + ".sock"),
+    }
+}
+
+// the commands a daemon can take over, as a request
+pub fn request_from_args(args: &ArgMatches) -> Option<Vec<String>> {
+    let location = |matches: &ArgMatches| matches.get_one::<String>("location").unwrap().clone();
+
+    match args.subcommand() {
+        Some((cmd @ ("set" | "reload" | "start" | "kill"), matches)) => {
+            Some(vec![cmd.to_owned(), location(matches)])
+        }
+        Some(("status", _)) => Some(vec![
+            "status".to_owned(),
+            Format::from_args(args).name().to_owned(),
+        ]),
+        Some(("profile", matches)) => match matches.subcommand() {
+            Some(("set", pmatches)) => Some(vec![
+                "profile-set".to_owned(),
+                pmatches.get_one::<String>("Profile").unwrap().clone(),
+            ]),
+            _ => None,
+        },
+        _ => None,
+    }
+}
+
+// hands the request to a running daemon, or does it here when there is none
+pub fn send_or(
+    hub: &Hub,
+    use_daemon: bool,
+    request: &[&str],
+    local: impl FnOnce(&Hub) -> Result<()>,
+) -> Result<()> {
+    if use_daemon {
+        let request: Vec<String> = request.iter().map(|part| part.to_string()).collect();
+
+        if send(&hub.path, &request)?.is_some() {
+            return Ok(());
+        }
+    }
+
+    local(hub)
+}
+
+// sends a request to the daemon, 'None' if there is no daemon running
+pub fn send(hub_path: &Path, request: &[String]) -> Result<Option<String>> {
+    let mut stream = match UnixStream::connect(socket_path(hub_path)) {
+        Ok(stream) => stream,
+        Err(_) => return Ok(None),
+    };
+
+    writeln!(stream, "{}", request.join("\t")).context("Couldn't talk to the daemon.")?;
+
+    let mut reply = String::new();
+    stream
+        .read_to_string(&mut reply)
+        .context("Couldn't read the daemon's reply.")?;
+
+    match reply.split_once('\n') {
+        Some(("ok", body)) => Ok(Some(body.to_owned())),
+        Some((_, body)) => bail!("{}", body.trim_end()),
+        None => bail!("The daemon sent a broken reply."),
+    }
+}
+
+pub fn serve(mut hub: Hub) -> Result<()> {
+    let socket = socket_path(&hub.path);
+
+    if UnixStream::connect(&socket).is_ok() {
+        bail!(
+            "A dothub daemon is already running at '{}'.",
+            socket.display()
+        );
+    }
+
+    // left over from a daemon that didn't exit cleanly
+    let _ = fs::remove_file(&socket);
+
+    let listener = UnixListener::bind(&socket)
+        .with_context(|| format!("Couldn't listen on '{}'.", socket.display()))?;
+    listener
+        .set_nonblocking(true)
+        .context("Couldn't set up the socket.")?;
+
+    // commands run by the daemon (profile 'start' for example) must not be handed back to it
+    env::set_var("DOTHUB_NO_DAEMON", "1");
+
+    let (tx, rx) = mpsc::channel();
+
+    let mut watcher = PollWatcher::new(
+        tx,
+        Config::default().with_poll_interval(Duration::from_secs(2)),
+    )
+    .context("Couldn't create watcher")?;
+
+    watch_hub(&mut watcher, &hub.path)?;
+
+    let mut supervisor = Supervisor::default();
+    let mut scheduler = Scheduler::new(&hub.path);
+
+    println!("dothub daemon is listening on '{}'.", socket.display());
+
+    loop {
+        // parse the hub again after it changed
+        // (drain every pending event, not just the first one)
+        if rx.try_iter().filter(|res| res.is_ok()).count() > 0 {
+            condition::forget_commands();
+
+            // a DotFolder that was just created has to be watched too
+            if let Err(e) = watch_hub(&mut watcher, &hub.path) {
+                println!("{:?}", e);
+            }
+
+            match Hub::load(&hub.path) {
+                Ok(new_hub) => hub = new_hub,
+                Err(e) => println!(
+                    "Couldn't parse the changed .dothub, keeping the old one: {:?}",
+                    e
+                ),
+            }
+        }
+
+        supervisor.check();
+        scheduler.tick();
+
+        match listener.accept() {
+            Ok((stream, _)) => handle(&mut hub, &mut supervisor, stream),
+            Err(e) if e.kind() == ErrorKind::WouldBlock => {
+                thread::sleep(Duration::from_millis(100))
+            }
+            Err(e) => println!("Couldn't accept a connection: {}", e),
+        }
+    }
+}
+
+// the hub's root and every folder 'process_hub' reads, not .git and the other hidden ones, the
+// poll walks everything it watches
+fn watch_hub(watcher: &mut PollWatcher, hub_path: &Path) -> Result<()> {
+    watcher
+        .watch(hub_path, RecursiveMode::NonRecursive)
+        .context("Couldn't add .dothub to watcher.")?;
+
+    for entry in fs::read_dir(hub_path).context("Couldn't read .dothub .")? {
+        let path = entry.context("Couldn't read .dothub .")?.path();
+
+        let hidden = path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+
+        if path.is_dir() && !hidden {
+            watcher
+                .watch(&path, RecursiveMode::Recursive)
+                .with_context(|| format!("Couldn't add '{}' to watcher.", path.display()))?;
+        }
+    }
+
+    Ok(())
+}
+
+fn handle(hub: &mut Hub, supervisor: &mut Supervisor, mut stream: UnixStream) {
+    let _ = stream.set_nonblocking(false);
+    let _ = stream.set_read_timeout(Some(Duration::from_secs(5)));
+
+    let mut line = String::new();
+
+    let reply = match BufReader::new(&stream).read_line(&mut line) {
+        Ok(_) => execute(hub, supervisor, line.trim_end()),
+        Err(e) => Err(e.into()),
+    };
+
+    let reply = match reply {
+        Ok(body) => format!("ok\n{}", body),
+        Err(e) => format!("error\n{:?}", e),
+    };
+
+    let _ = stream.write_all(reply.as_bytes());
+}
+
+fn execute(hub: &mut Hub, supervisor: &mut Supervisor, request: &str) -> Result<String> {
+    let request: Vec<&str> = request.split('\t').collect();
+
+    // the watcher polls, and a .dothub edited a moment ago has to be used like without the
+    // daemon, so everything that applies a config parses the hub again
+    if changes(&request) {
+        condition::forget_commands();
+        *hub = Hub::load(&hub.path).context("Couldn't read your .dothub")?;
+    }
+
+    let hub = &*hub;
+
+    match request.as_slice() {
+        ["set", location] => hub.set(location).map(|_| String::new()),
+        ["profile-set", name] => hub.profile_set(name).map(|_| String::new()),
+        ["reload", location] => supervisor.reload(hub, location),
+        ["start", location] => supervisor.start(hub, location),
+        ["kill", location] => supervisor.kill(hub, location),
+        ["status", format] => {
+            let status = output::Status {
+                supervised: supervisor.status(),
+                ..hub.status()
+            };
+
+            output::render(&status, Format::from_name(format))
+        }
+        _ => bail!("Unknown request '{}'.", request.join(" ")),
+    }
+}
+
+// the requests that read a config
+fn changes(request: &[&str]) -> bool {
+    matches!(
+        request,
+        ["set" | "reload" | "start" | "kill" | "profile-set", _]
+    )
+}
+
+struct Program {
+    command: String,
+    child: Child,
+    started: Instant,
+    restarts: u32,
+    exited: Option<ExitStatus>,
+}
+
+// keeps track of the programs started through the daemon
+#[derive(Default)]
+struct Supervisor {
+    programs: BTreeMap<String, Program>,
+    // killed, but not reaped yet
+    stopping: Vec<Child>,
+}
+
+impl Supervisor {
+    fn start(&mut self, hub: &Hub, location: &str) -> Result<String> {
+        let config = get_active_config(hub.find(location)?)?;
+        let command = config
+            .start
+            .context("No 'start' command specified in any .dothub .")?;
+
+        if let Some(program) = self.programs.get(location) {
+            if program.exited.is_none() {
+                bail!(
+                    "'{}' is already running, pid {}.",
+                    location,
+                    program.child.id()
+                );
+            }
+        }
+
+        let child = spawn(&command)?;
+
+        self.programs.insert(
+            location.to_owned(),
+            Program {
+                command,
+                child,
+                started: Instant::now(),
+                restarts: 0,
+                exited: None,
+            },
+        );
+
+        Ok(String::new())
+    }
+
+    fn kill(&mut self, hub: &Hub, location: &str) -> Result<String> {
+        let config = get_active_config(hub.find(location)?)?;
+
+        // forget it first, so it doesn't get restarted
+        let program = self.programs.remove(location);
+
+        match (&config.kill, program) {
+            (Some(_), program) => {
+                dot_kill(&config)?;
+                self.stopping.extend(program.map(|p| p.child));
+            }
+            (None, Some(mut program)) => {
+                let _ = program.child.kill();
+                self.stopping.push(program.child);
+            }
+            (None, None) => bail!("No 'kill' command specified in any .dothub ."),
+        }
+
+        Ok(String::new())
+    }
+
+    fn reload(&mut self, hub: &Hub, location: &str) -> Result<String> {
+        let config = get_active_config(hub.find(location)?)?;
+
+        // 'kill' + 'start' of a supervised program has to go through the supervisor
+        let supervised = self
+            .programs
+            .get(location)
+            .is_some_and(|p| p.exited.is_none());
+
+        if config.reload.is_none() && supervised {
+            self.kill(hub, location)?;
+            self.start(hub, location)
+        } else {
+            dot_reload(&config).map(|_| String::new())
+        }
+    }
+
+    // reap exited programs and restart the ones that crashed
+    fn check(&mut self) {
+        self.stopping
+            .retain_mut(|child| matches!(child.try_wait(), Ok(None)));
+
+        for (location, program) in self.programs.iter_mut() {
+            if program.exited.is_some() {
+                continue;
+            }
+
+            let status = match program.child.try_wait() {
+                Ok(Some(status)) => status,
+                _ => continue,
+            };
+
+            if status.success() {
+                program.exited = Some(status);
+                continue;
+            }
+
+            if program.started.elapsed() > STABLE_AFTER {
+                program.restarts = 0;
+            }
+
+            if program.restarts >= MAX_RESTARTS {
+                println!("'{}' keeps crashing, not restarting it.", location);
+                program.exited = Some(status);
+                continue;
+            }
+
+            println!("'{}' exited with {}, restarting it.", location, status);
+
+            match spawn(&program.command) {
+                Ok(child) => {
+                    program.child = child;
+                    program.started = Instant::now();
+                    program.restarts += 1;
+                }
+                Err(e) => {
+                    println!("{:?}", e);
+                    program.exited = Some(status);
+                }
+            }
+        }
+    }
+
+    fn status(&self) -> Vec<output::Supervised> {
+        self.programs
+            .iter()
+            .map(|(location, program)| output::Supervised {
+                location: location.clone(),
+                running: program.exited.is_none(),
+                pid: program.exited.is_none().then(|| program.child.id()),
+                exit: program.exited.map(|exit| exit.to_string()),
+            })
+            .collect()
+    }
+}
+
+fn spawn(command: &str) -> Result<Child> {
+    settings::shell_command(command)
+        .spawn()
+        .with_context(|| format!("Couldn't start '{}'.", command))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn requests_use_the_edited_dothub() {
+        let root = env::temp_dir().join(format!("dothub-daemon-{}", std::process::id()));
+        let hub_path = root.join("hub");
+
+        fs::create_dir_all(hub_path.join("profiles")).unwrap();
+        fs::create_dir_all(hub_path.join("waybar/neon")).unwrap();
+        fs::write(hub_path.join("waybar/neon/config"), "").unwrap();
+        fs::write(
+            hub_path.join("waybar/.dothub"),
+            format!("destination = '{}'", root.join("waybar").display()),
+        )
+        .unwrap();
+
+        let mut hub = Hub::load(&hub_path).unwrap();
+
+        fs::write(
+            hub_path.join("waybar/neon/.dothub"),
+            format!("destination = '{}'", root.join("elsewhere").display()),
+        )
+        .unwrap();
+
+        let reply = execute(&mut hub, &mut Supervisor::default(), "set\twaybar/neon");
+        let linked = fs::read_link(root.join("elsewhere"));
+
+        fs::remove_dir_all(&root).unwrap();
+
+        reply.unwrap();
+        assert_eq!(linked.unwrap(), hub_path.join("waybar/neon"));
+    }
+}
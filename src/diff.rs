@@ -0,0 +1,235 @@
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use clap::ArgMatches;
+use similar::TextDiff;
+
+use crate::{
+    dot_files, dot_mapped_links, get::contract_home, get_active_config, layer, Dot, DotFolder, Hub,
+};
+
+// one side of a diff, by what the two sides have in common, with the name that is shown and the
+// file to read
+type Files = BTreeMap<PathBuf, (String, PathBuf)>;
+
+// 'dothub diff', what setting a Dot would change: against another Dot, or against what is at
+// its destination right now
+pub fn diff(matches: &ArgMatches, hub: &Hub) -> Result<()> {
+    let location = matches.get_one::<String>("location").unwrap();
+    let (dotfolder, dot) = find_dot(hub, location)?;
+
+    let (old, new) = match matches.get_one::<String>("other") {
+        // a Dot of the same DotFolder, or 'DotFolder/Dot'
+        Some(other) => {
+            let other = match other.contains('/') {
+                true => other.to_owned(),
+                false => format!("{}/{}", dotfolder.name, other),
+            };
+            let (other_dotfolder, other_dot) = find_dot(hub, &other)?;
+
+            (
+                dot_view(hub, dotfolder, dot)?,
+                dot_view(hub, other_dotfolder, other_dot)?,
+            )
+        }
+        None => {
+            let new = dot_targets(hub, dotfolder, dot)?;
+
+            (deployed(dotfolder, dot, &new)?, new)
+        }
+    };
+
+    print!("{}", render(&old, &new));
+
+    Ok(())
+}
+
+fn find_dot<'a>(hub: &'a Hub, location: &str) -> Result<(&'a DotFolder, &'a Dot)> {
+    let (dotfolder, dot) = hub.find(location)?;
+    let dot = dot.context("You have to specify a Dot, example 'waybar/neon'.")?;
+
+    Ok((dotfolder, dot))
+}
+
+// every file of a Dot with its layers, as 'DotFolder/Dot/file'
+fn dot_view(hub: &Hub, dotfolder: &DotFolder, dot: &Dot) -> Result<Files> {
+    let view = layer::dot_view(&hub.dot_path(dotfolder, dot))?;
+
+    Ok(view
+        .into_iter()
+        .map(|(file, path)| {
+            let name = format!("{}/{}/{}", dotfolder.name, dot.name, file.display());
+            (file, (name, path))
+        })
+        .collect())
+}
+
+// the Dot's files by the place 'set' links them to
+fn dot_targets(hub: &Hub, dotfolder: &DotFolder, dot: &Dot) -> Result<Files> {
+    let config = get_active_config((dotfolder, Some(dot)))?;
+    let view = dot_view(hub, dotfolder, dot)?;
+
+    let mut targets = Files::new();
+
+    if !config.destination.is_empty() {
+        for (file, named) in &view {
+            targets.insert(Path::new(&config.destination).join(file), named.clone());
+        }
+    }
+
+    // relative to the Dot, a [[links]] source can be a folder of it too
+    for (link, source) in dot_mapped_links(&config, Path::new(""))? {
+        for (file, named) in &view {
+            if let Ok(rest) = file.strip_prefix(&source) {
+                targets.insert(link.join(rest), named.clone());
+            }
+        }
+    }
+
+    Ok(targets)
+}
+
+// what is at the places the Dot links to right now: all of 'destination', only the entries of
+// the Dot with 'find_and_replace', and the [[links]]
+fn deployed(dotfolder: &DotFolder, dot: &Dot, targets: &Files) -> Result<Files> {
+    let config = get_active_config((dotfolder, Some(dot)))?;
+    let destination = Path::new(&config.destination);
+
+    let mut roots = BTreeSet::new();
+
+    if !config.destination.is_empty() {
+        match config.find_and_replace {
+            Some(true) => roots.extend(
+                targets
+                    .keys()
+                    .filter_map(|target| target.strip_prefix(destination).ok())
+                    .filter_map(|file| file.components().next())
+                    .map(|entry| destination.join(entry)),
+            ),
+            _ => {
+                roots.insert(destination.to_path_buf());
+            }
+        }
+    }
+
+    for (link, _) in dot_mapped_links(&config, Path::new(""))? {
+        roots.insert(link);
+    }
+
+    let mut deployed = Files::new();
+
+    for root in roots {
+        for file in files_at(&root)? {
+            deployed.insert(file.clone(), (contract_home(&file), file));
+        }
+    }
+
+    Ok(deployed)
+}
+
+// a file, or every file in a folder, through the symlinks 'set' made, without the .dothub of a
+// linked Dot, like in 'layer::dot_view'
+fn files_at(path: &Path) -> Result<Vec<PathBuf>> {
+    match fs::metadata(path) {
+        Ok(meta) if meta.is_dir() => Ok(dot_files(path)?
+            .into_iter()
+            .filter(|file| file != Path::new(".dothub"))
+            .map(|file| path.join(file))
+            .collect()),
+        Ok(_) => Ok(vec![path.to_path_buf()]),
+        Err(_) => Ok(vec![]),
+    }
+}
+
+// the added and removed files first, then a unified diff of every file that changed, the files
+// are compared as they are, dothub has no ignore rules or templates
+fn render(old: &Files, new: &Files) -> String {
+    let mut listed = String::new();
+    let mut diffs = String::new();
+
+    let keys: BTreeSet<&PathBuf> = old.keys().chain(new.keys()).collect();
+
+    for key in keys {
+        let (old_name, old_path, new_name, new_path) = match (old.get(key), new.get(key)) {
+            (Some((old_name, old_path)), Some((new_name, new_path))) => {
+                (old_name, old_path, new_name, new_path)
+            }
+            (None, Some((name, _))) => {
+                listed += &format!("added    {}\n", name);
+                continue;
+            }
+            (Some((name, _)), None) => {
+                listed += &format!("removed  {}\n", name);
+                continue;
+            }
+            (None, None) => unreachable!(),
+        };
+
+        let old_contents = fs::read(old_path).unwrap_or_default();
+        let new_contents = fs::read(new_path).unwrap_or_default();
+
+        if old_contents == new_contents {
+            continue;
+        }
+
+        // like diff, a NUL byte makes a file binary
+        if old_contents.contains(&0) || new_contents.contains(&0) {
+            diffs += &format!("Binary files {} and {} differ\n", old_name, new_name);
+            continue;
+        }
+
+        diffs += &TextDiff::from_lines(
+            String::from_utf8_lossy(&old_contents),
+            String::from_utf8_lossy(&new_contents),
+        )
+        .unified_diff()
+        .header(old_name, new_name)
+        .to_string();
+    }
+
+    match listed.is_empty() || diffs.is_empty() {
+        true => listed + &diffs,
+        false => listed + "\n" + &diffs,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{env, process};
+
+    #[test]
+    fn added_removed_and_changed_files() {
+        let root = env::temp_dir().join(format!("dothub-diff-{}", process::id()));
+        fs::create_dir_all(&root).unwrap();
+
+        for (name, contents) in [("a", "1\n"), ("b", "2\n"), ("c", "3\n"), (".dothub", "")] {
+            fs::write(root.join(name), contents).unwrap();
+        }
+
+        let files = files_at(&root).unwrap();
+        let side = |names: &[(&str, &str)]| -> Files {
+            names
+                .iter()
+                .map(|(key, file)| (PathBuf::from(key), (key.to_string(), root.join(file))))
+                .collect()
+        };
+
+        let rendered = render(
+            &side(&[("same", "a"), ("changed", "b"), ("removed", "a")]),
+            &side(&[("same", "a"), ("changed", "c"), ("added", "a")]),
+        );
+
+        fs::remove_dir_all(&root).unwrap();
+
+        assert_eq!(files.len(), 3);
+        assert_eq!(
+            rendered,
+            "added    added\nremoved  removed\n\n--- changed\n+++ changed\n@@ -1 +1 @@\n-2\n+3\n"
+        );
+    }
+}
@@ -0,0 +1,407 @@
+use std::{
+    collections::BTreeSet,
+    env, fs,
+    os::unix::fs::PermissionsExt,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Result};
+
+use crate::{
+    dot_entries, dot_mapped_links, get::contract_home, get_active_config, hub_defaults, layer,
+    schema, Dot, DotFolder, PartialConfig, ProfileParsable,
+};
+
+// commands of the shell itself, they aren't on $PATH
+const BUILTINS: [&str; 16] = [
+    "cd", "exec", "export", "eval", "source", ".", "if", "for", "while", "case", "test", "[",
+    "echo", "printf", "kill", "true",
+];
+
+// a link 'set' creates, and the Dot it belongs to
+struct Target {
+    path: PathBuf,
+    dotfolder: String,
+    dot: String,
+}
+
+#[derive(Default)]
+struct Report {
+    problems: Vec<String>,
+    warnings: Vec<String>,
+}
+
+impl Report {
+    fn problem(&mut self, at: &str, message: String) {
+        self.problems.push(format!("{}: {}", at, message));
+    }
+
+    fn warning(&mut self, at: &str, message: String) {
+        self.warnings.push(format!("{}: {}", at, message));
+    }
+}
+
+// 'dothub doctor', goes through the whole hub without stopping at the first problem
+pub fn doctor(folder_path: &Path) -> Result<()> {
+    let mut report = Report::default();
+    let mut dots = BTreeSet::new();
+    let mut targets = vec![];
+
+    let defaults = match hub_defaults(folder_path) {
+        Ok(defaults) => defaults,
+        Err(e) => {
+            report.problem("config.toml", format!("{}", e));
+            PartialConfig::default()
+        }
+    };
+
+    for (dotfolder, dotfolder_path) in children(&mut report, folder_path, ".dothub") {
+        if dotfolder == "profiles" {
+            continue;
+        }
+
+        let config_path = dotfolder_path.join(".dothub");
+        let at = format!("{}/.dothub", dotfolder);
+
+        let config = if config_path.is_file() {
+            check_config(&mut report, &at, &config_path)
+        } else {
+            report.problem(
+                &dotfolder,
+                "there is no .dothub, every DotFolder needs one.".to_owned(),
+            );
+            None
+        };
+
+        let dotfolder_struct = DotFolder {
+            name: dotfolder.clone(),
+            dots: vec![],
+            config,
+            defaults: defaults.clone(),
+        };
+
+        for (dot, dot_path) in children(&mut report, &dotfolder_path, &dotfolder) {
+            let location = format!("{}/{}", dotfolder, dot);
+            dots.insert(location.clone());
+
+            let dot_config_path = dot_path.join(".dothub");
+            let dot_struct = Dot {
+                name: dot.clone(),
+                config: match dot_config_path.is_file() {
+                    true => {
+                        let at = format!("{}/.dothub", location);
+
+                        match check_config(&mut report, &at, &dot_config_path) {
+                            Some(config) => Some(config),
+                            // already reported
+                            None => continue,
+                        }
+                    }
+                    false => None,
+                },
+            };
+
+            check_dot(
+                &mut report,
+                (&dotfolder_struct, &dot_struct),
+                &dot_path,
+                &mut targets,
+            );
+        }
+    }
+
+    for (name, path) in children(&mut report, &folder_path.join("profiles"), "profiles") {
+        check_profile(&mut report, &format!("profiles/{}", name), &path, &dots);
+    }
+
+    check_targets(&mut report, &targets);
+
+    for warning in &report.warnings {
+        println!("warning: {}", warning);
+    }
+    for problem in &report.problems {
+        println!("problem: {}", problem);
+    }
+
+    match report.problems.len() {
+        0 => {
+            println!("No problems found in your .dothub .");
+            Ok(())
+        }
+        count => bail!("Found {} problem(s) in your .dothub .", count),
+    }
+}
+
+// the visible folders (or files, in 'profiles') of a folder, by name
+fn children(report: &mut Report, path: &Path, at: &str) -> Vec<(String, PathBuf)> {
+    let entries = match fs::read_dir(path) {
+        Ok(entries) => entries,
+        Err(e) => {
+            report.problem(at, format!("couldn't be read, {}.", e));
+            return vec![];
+        }
+    };
+
+    let profiles = at == "profiles";
+    let mut children = vec![];
+
+    for entry in entries.filter_map(|entry| entry.ok()) {
+        let path = entry.path();
+
+        let name = match entry.file_name().into_string() {
+            Ok(name) => name,
+            Err(name) => {
+                report.problem(
+                    at,
+                    format!("'{}' isn't a UTF-8 name.", name.to_string_lossy()),
+                );
+                continue;
+            }
+        };
+
+        // profiles are files, DotFolders and Dots are folders
+        if name.starts_with('.') || path.is_dir() == profiles {
+            continue;
+        }
+
+        children.push((name, path));
+    }
+
+    children.sort();
+    children
+}
+
+// the parsed config, if it could be parsed
+fn check_config(report: &mut Report, at: &str, path: &Path) -> Option<PartialConfig> {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) => {
+            report.problem(at, format!("couldn't be read, {}.", e));
+            return None;
+        }
+    };
+
+    // unknown keys and wrong types, pointing at the line
+    let config: PartialConfig = match schema::parse_dothub(path, &contents) {
+        Ok(config) => config,
+        Err(e) => {
+            report.problem(at, e.to_string());
+            return None;
+        }
+    };
+
+    let mut commands = vec![&config.start, &config.kill, &config.reload];
+    commands.extend(config.notifications.as_ref().map(|n| &n.command));
+    for o in config.overrides.iter().flatten() {
+        commands.extend([&o.start, &o.kill, &o.reload]);
+    }
+
+    for command in commands.into_iter().flatten() {
+        check_command(report, at, command);
+    }
+
+    Some(config)
+}
+
+// only the program a command starts with is checked, the rest is up to the shell
+fn check_command(report: &mut Report, at: &str, command: &str) {
+    let program = match command
+        .split_whitespace()
+        .find(|word| !word.contains('=') || word.starts_with('='))
+    {
+        Some(program) => program,
+        None => return,
+    };
+
+    if BUILTINS.contains(&program) || on_path(program) {
+        return;
+    }
+
+    report.warning(
+        at,
+        format!("'{}' runs '{}', which isn't on $PATH.", command, program),
+    );
+}
+
+pub fn on_path(program: &str) -> bool {
+    let executable = |path: &Path| {
+        fs::metadata(path)
+            .is_ok_and(|meta| meta.is_file() && meta.permissions().mode() & 0o111 != 0)
+    };
+
+    if program.contains('/') {
+        return executable(Path::new(program));
+    }
+
+    env::var_os("PATH")
+        .map(|paths| env::split_paths(&paths).any(|dir| executable(&dir.join(program))))
+        .unwrap_or(false)
+}
+
+fn check_dot(
+    report: &mut Report,
+    (dotfolder, dot): (&DotFolder, &Dot),
+    dot_path: &Path,
+    targets: &mut Vec<Target>,
+) {
+    let location = format!("{}/{}", dotfolder.name, dot.name);
+
+    // a missing or broken DotFolder .dothub is already reported
+    if dotfolder.config.is_none() {
+        return;
+    }
+
+    let config = match get_active_config((dotfolder, Some(dot))) {
+        Ok(config) => config,
+        Err(e) => return report.problem(&location, format!("{}", e)),
+    };
+
+    if config.destination.is_empty() && config.links.is_none() {
+        report.warning(
+            &location,
+            "'destination' is empty and there are no [[links]], 'set' doesn't link anything. Without 'destination' the DotFolder's is used.".to_owned(),
+        );
+    }
+
+    let layers = match layer::layers(dot_path) {
+        Ok(layers) => layers,
+        Err(e) => return report.problem(&location, format!("{}", e)),
+    };
+
+    // what the merged tree would have, without building it
+    let mut entries = BTreeSet::new();
+    for layer in &layers {
+        if let Ok(layer_entries) = dot_entries(layer) {
+            entries.extend(layer_entries);
+        }
+    }
+
+    let conf_path = Path::new(&config.destination);
+    let mut paths = match config.find_and_replace {
+        _ if config.destination.is_empty() => vec![],
+        Some(true) => entries.iter().map(|entry| conf_path.join(entry)).collect(),
+        _ => vec![conf_path.to_path_buf()],
+    };
+
+    match dot_mapped_links(&config, dot_path) {
+        Ok(mapped) => {
+            for (link, source) in mapped {
+                let relative = source.strip_prefix(dot_path).unwrap();
+
+                if !layers.iter().any(|layer| layer.join(relative).exists()) {
+                    report.problem(
+                        &location,
+                        format!("'{}' from [[links]] isn't in the Dot.", relative.display()),
+                    );
+                }
+
+                paths.push(link);
+            }
+        }
+        Err(e) => report.problem(&location, format!("{}", e)),
+    }
+
+    for path in paths {
+        targets.push(Target {
+            path,
+            dotfolder: dotfolder.name.clone(),
+            dot: dot.name.clone(),
+        });
+    }
+}
+
+// two links at the same place, or one inside of a folder another one links
+fn check_targets(report: &mut Report, targets: &[Target]) {
+    let mut reported = BTreeSet::new();
+
+    for (i, a) in targets.iter().enumerate() {
+        for b in &targets[i + 1..] {
+            // the Dots of one DotFolder take turns at the same destination
+            if a.dotfolder == b.dotfolder && a.dot != b.dot {
+                continue;
+            }
+
+            let (outer, inner) = match (b.path.starts_with(&a.path), a.path.starts_with(&b.path)) {
+                (true, _) => (a, b),
+                (_, true) => (b, a),
+                _ => continue,
+            };
+
+            if !reported.insert((outer.path.clone(), inner.path.clone())) {
+                continue;
+            }
+
+            let message = match outer.path == inner.path {
+                true => format!(
+                    "'{}' is linked by both of them.",
+                    contract_home(&outer.path)
+                ),
+                false => format!(
+                    "'{}' is inside of '{}', which is a link already.",
+                    contract_home(&inner.path),
+                    contract_home(&outer.path)
+                ),
+            };
+
+            report.problem(
+                &format!(
+                    "{}/{} and {}/{}",
+                    outer.dotfolder, outer.dot, inner.dotfolder, inner.dot
+                ),
+                message,
+            );
+        }
+    }
+
+    // 'set' removes whatever is there, only links are expected
+    let mut seen = BTreeSet::new();
+
+    for target in targets {
+        let is_real = fs::symlink_metadata(&target.path).is_ok_and(|meta| !meta.is_symlink());
+
+        if is_real && seen.insert(target.path.clone()) {
+            report.warning(
+                &format!("{}/{}", target.dotfolder, target.dot),
+                format!(
+                    "'{}' isn't a link, 'set' replaces it. Use 'dothub get --adopt' to keep it.",
+                    contract_home(&target.path)
+                ),
+            );
+        }
+    }
+}
+
+fn check_profile(report: &mut Report, at: &str, path: &Path, dots: &BTreeSet<String>) {
+    let contents = match fs::read_to_string(path) {
+        Ok(contents) => contents,
+        Err(e) => return report.problem(at, format!("couldn't be read, {}.", e)),
+    };
+
+    let profile: ProfileParsable = match schema::parse(path, &contents) {
+        Ok(profile) => profile,
+        Err(e) => return report.problem(at, e.to_string()),
+    };
+
+    let mut starts = profile.start.into_iter().flatten().collect::<Vec<_>>();
+    let mut references = profile.dots.into_iter().flatten().collect::<Vec<_>>();
+
+    for o in profile.overrides {
+        starts.extend(o.start.into_iter().flatten());
+        references.extend(o.dots.into_iter().flatten());
+    }
+
+    for command in starts {
+        check_command(report, at, &command);
+    }
+
+    references.sort();
+    references.dedup();
+
+    for (dotfolder, dot) in references {
+        let location = format!("{}/{}", dotfolder, dot);
+
+        if !dots.contains(&location) {
+            report.problem(at, format!("'{}' doesn't exist.", location));
+        }
+    }
+}
@@ -0,0 +1,400 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use anyhow::{bail, Context, Result};
+use clap::ArgMatches;
+use toml_edit::{DocumentMut, TableLike};
+
+use crate::{
+    dot_is_active, dot_set, dot_unlink, get::copy_tree, get::print_skipped, get_active_config,
+    layer, resolve_config, DotConfig, Hub,
+};
+
+// 'dothub dot cp|mv|rm'
+pub fn dot(matches: &ArgMatches, hub: &Hub) -> Result<()> {
+    match matches.subcommand() {
+        Some(("cp", matches)) => copy(
+            hub,
+            matches.get_one::<String>("location").unwrap(),
+            matches.get_one::<String>("to").unwrap(),
+        ),
+        Some(("mv", matches)) => rename(
+            hub,
+            matches.get_one::<String>("location").unwrap(),
+            matches.get_one::<String>("to").unwrap(),
+        ),
+        Some(("rm", matches)) => remove(
+            hub,
+            matches.get_one::<String>("location").unwrap(),
+            matches.get_flag("force"),
+        ),
+        _ => unreachable!(),
+    }
+}
+
+fn copy(hub: &Hub, location: &str, to: &str) -> Result<()> {
+    let (dotfolder, dot) = find_dot(hub, location)?;
+    let (to_dotfolder, to_dot) = target(hub, &dotfolder, to)?;
+
+    let mut skipped = vec![];
+    copy_tree(
+        &hub.path.join(&dotfolder).join(&dot),
+        &hub.path.join(&to_dotfolder).join(&to_dot),
+        &mut skipped,
+    )
+    .context("Couldn't copy the Dot.")?;
+
+    // a bare 'extends' means a Dot of the DotFolder it's in
+    if to_dotfolder != dotfolder {
+        qualify_extends(&hub.path.join(&to_dotfolder).join(&to_dot), &dotfolder)?;
+    }
+
+    print_skipped(&skipped);
+    println!("Copied '{}' to '{}/{}'.", location, to_dotfolder, to_dot);
+
+    Ok(())
+}
+
+fn rename(hub: &Hub, location: &str, to: &str) -> Result<()> {
+    let (dotfolder, dot) = find_dot(hub, location)?;
+    let (to_dotfolder, to_dot) = target(hub, &dotfolder, to)?;
+
+    // profiles map DotFolders to Dots, moving between DotFolders can't be followed
+    if to_dotfolder != dotfolder {
+        bail!("Dots can only be renamed inside of their DotFolder, use 'dot cp' and 'dot rm'.");
+    }
+
+    let dotfolder_path = hub.path.join(&dotfolder);
+    let (dot_path, to_path) = (dotfolder_path.join(&dot), dotfolder_path.join(&to_dot));
+    let active = active_config(hub, location)?;
+
+    fs::rename(&dot_path, &to_path).context("Couldn't rename the Dot.")?;
+
+    for profile in update_profiles(&hub.path, &dotfolder, &dot, Some(&to_dot))? {
+        println!("Updated profile '{}'.", profile);
+    }
+
+    for extending in update_extends(&hub.path, &dotfolder, &dot, &to_dot)? {
+        println!("Updated 'extends' of '{}'.", extending);
+    }
+
+    // merged trees of the Dots extending it still link to the old name
+    for extending in layer::extended_by(&hub.path, &dotfolder, &to_dot)? {
+        let extending_path = hub.path.join(&extending);
+
+        if layer::merged_path(&extending_path).exists() {
+            let config = resolve_config(extending_path.parent().unwrap(), &extending_path)?;
+            layer::materialize(&config, &extending_path)?;
+        }
+    }
+
+    // point the links at the new name, the content didn't change so there is nothing to reload
+    if let Some(config) = active {
+        dot_unlink(&config, &dot_path)?;
+
+        let config = DotConfig {
+            reload_on_set: Some(false),
+            ..resolve_config(&dotfolder_path, &to_path)?
+        };
+        dot_set(&config, &to_path, Path::new(&config.destination))?;
+
+        println!("Linked '{}' again.", config.destination);
+    }
+
+    layer::remove_merged(&dot_path)?;
+
+    println!("Renamed '{}' to '{}/{}'.", location, dotfolder, to_dot);
+
+    Ok(())
+}
+
+fn remove(hub: &Hub, location: &str, force: bool) -> Result<()> {
+    let (dotfolder, dot) = find_dot(hub, location)?;
+    let active = active_config(hub, location)?;
+
+    let profiles: Vec<&str> = hub
+        .profiles
+        .iter()
+        .filter(|profile| {
+            let uses = |dots: &Option<HashMap<String, String>>| {
+                dots.as_ref()
+                    .is_some_and(|dots| dots.get(&dotfolder) == Some(&dot))
+            };
+
+            uses(&profile.dots) || profile.overrides.iter().any(|o| uses(&o.dots))
+        })
+        .map(|profile| profile.name.as_str())
+        .collect();
+
+    // their merged trees would lose the files, so it's never done on its own
+    let extending = layer::extended_by(&hub.path, &dotfolder, &dot)?;
+
+    if !extending.is_empty() {
+        bail!(
+            "'{}' is extended by {}, change their 'extends' first.",
+            location,
+            extending.join(", ")
+        );
+    }
+
+    if !force {
+        if active.is_some() {
+            bail!(
+                "'{}' is set right now, use '--force' to delete it anyway.",
+                location
+            );
+        }
+
+        if !profiles.is_empty() {
+            bail!(
+                "'{}' is used by the profiles {}, use '--force' to delete it and remove it from them.",
+                location,
+                profiles.join(", ")
+            );
+        }
+    }
+
+    let dot_path = hub.path.join(&dotfolder).join(&dot);
+
+    if let Some(config) = active {
+        dot_unlink(&config, &dot_path)?;
+        println!("Removed the links at '{}'.", config.destination);
+    }
+
+    fs::remove_dir_all(&dot_path).context("Couldn't delete the Dot.")?;
+    layer::remove_merged(&dot_path)?;
+
+    for profile in update_profiles(&hub.path, &dotfolder, &dot, None)? {
+        println!("Removed it from profile '{}'.", profile);
+    }
+
+    println!("Deleted '{}'.", location);
+
+    Ok(())
+}
+
+fn find_dot(hub: &Hub, location: &str) -> Result<(String, String)> {
+    match hub.find(location)? {
+        (dotfolder, Some(dot)) => Ok((dotfolder.name.clone(), dot.name.clone())),
+        (_, None) => bail!("You have to specify the Dot, example 'waybar/neon'."),
+    }
+}
+
+// the config of the Dot, if it's linked right now
+fn active_config(hub: &Hub, location: &str) -> Result<Option<DotConfig>> {
+    let (dotfolder, dot) = hub.find(location)?;
+    let dot = dot.context("You have to specify the Dot, example 'waybar/neon'.")?;
+
+    // a DotFolder without a .dothub has nothing linked
+    let config = match dotfolder.config {
+        Some(_) => get_active_config((dotfolder, Some(dot)))?,
+        None => return Ok(None),
+    };
+
+    match dot_is_active(&config, &hub.dot_path(dotfolder, dot)) {
+        true => Ok(Some(config)),
+        false => Ok(None),
+    }
+}
+
+// a new name in the same DotFolder, or 'DotFolder/Dot'
+fn target(hub: &Hub, dotfolder: &str, to: &str) -> Result<(String, String)> {
+    let (to_dotfolder, to_dot) = match to.split_once('/') {
+        Some((to_dotfolder, to_dot)) => (to_dotfolder, to_dot),
+        None => (dotfolder, to),
+    };
+
+    if to_dot.is_empty() || to_dot.starts_with('.') || to_dot.contains('/') {
+        bail!("'{}' isn't a valid name for a Dot.", to_dot);
+    }
+
+    let (to_dotfolder, _) = hub.find(to_dotfolder)?;
+
+    if hub.path.join(&to_dotfolder.name).join(to_dot).exists() {
+        bail!("Dot '{}/{}' already exists.", to_dotfolder.name, to_dot);
+    }
+
+    Ok((to_dotfolder.name.clone(), to_dot.to_owned()))
+}
+
+// points (or with 'None' removes) every 'dotfolder = "dot"' of the profiles,
+// keeps the rest of the files as they are, returns the changed profiles
+fn update_profiles(
+    folder_path: &Path,
+    dotfolder: &str,
+    dot: &str,
+    to: Option<&str>,
+) -> Result<Vec<String>> {
+    let mut updated = vec![];
+
+    for entry in fs::read_dir(folder_path.join("profiles")).context("Couldn't read profiles.")? {
+        let path = entry.context("Couldn't read profiles.")?.path();
+
+        // every file is a profile, like in 'process_hub'
+        if !path.is_file() {
+            continue;
+        }
+
+        let contents = fs::read_to_string(&path)
+            .with_context(|| format!("Couldn't read '{}'.", path.display()))?;
+        let mut document: DocumentMut = contents
+            .parse()
+            .with_context(|| format!("'{}' couldn't be parsed.", path.display()))?;
+
+        let mut changed = false;
+
+        // 'dots' and the 'dots' of every [[overrides]]
+        if let Some(dots) = document
+            .get_mut("dots")
+            .and_then(|dots| dots.as_table_like_mut())
+        {
+            changed |= update_dots(dots, dotfolder, dot, to);
+        }
+
+        if let Some(overrides) = document
+            .get_mut("overrides")
+            .and_then(|overrides| overrides.as_array_of_tables_mut())
+        {
+            for o in overrides.iter_mut() {
+                if let Some(dots) = o.get_mut("dots").and_then(|dots| dots.as_table_like_mut()) {
+                    changed |= update_dots(dots, dotfolder, dot, to);
+                }
+            }
+        }
+
+        if !changed {
+            continue;
+        }
+
+        fs::write(&path, document.to_string())
+            .with_context(|| format!("Couldn't write '{}'.", path.display()))?;
+
+        updated.push(path.file_stem().unwrap().to_string_lossy().into_owned());
+    }
+
+    updated.sort();
+
+    Ok(updated)
+}
+
+fn update_dots(dots: &mut dyn TableLike, dotfolder: &str, dot: &str, to: Option<&str>) -> bool {
+    if dots.get(dotfolder).and_then(|item| item.as_str()) != Some(dot) {
+        return false;
+    }
+
+    match to {
+        Some(to) => {
+            let value = dots.get_mut(dotfolder).unwrap().as_value_mut().unwrap();
+            let decor = value.decor().clone();
+
+            *value = to.into();
+            *value.decor_mut() = decor;
+        }
+        None => {
+            dots.remove(dotfolder);
+        }
+    }
+
+    true
+}
+
+// points the 'extends' of other Dots at the new name, returns the changed Dots
+fn update_extends(folder_path: &Path, dotfolder: &str, dot: &str, to: &str) -> Result<Vec<String>> {
+    let mut updated = vec![];
+
+    for (df, d) in layer::all_dots(folder_path)? {
+        let path = folder_path.join(&df).join(&d).join(".dothub");
+
+        if !path.is_file() {
+            continue;
+        }
+
+        let contents = fs::read_to_string(&path)
+            .with_context(|| format!("Couldn't read '{}'.", path.display()))?;
+        let mut document: DocumentMut = contents
+            .parse()
+            .with_context(|| format!("'{}' couldn't be parsed.", path.display()))?;
+
+        let value = match document
+            .get_mut("extends")
+            .and_then(|extends| extends.as_value_mut())
+        {
+            Some(value) => value,
+            None => continue,
+        };
+
+        // 'dot' only means this one from inside of the same DotFolder
+        let new = match value.as_str() {
+            Some(extends) if df == dotfolder && extends == dot => to.to_owned(),
+            Some(extends) if extends == format!("{}/{}", dotfolder, dot) => {
+                format!("{}/{}", dotfolder, to)
+            }
+            _ => continue,
+        };
+
+        let decor = value.decor().clone();
+        *value = new.into();
+        *value.decor_mut() = decor;
+
+        fs::write(&path, document.to_string())
+            .with_context(|| format!("Couldn't write '{}'.", path.display()))?;
+
+        updated.push(format!("{}/{}", df, d));
+    }
+
+    Ok(updated)
+}
+
+// turns 'extends = "base"' of a copied Dot (and of its [[overrides]]) into "dotfolder/base", so
+// it keeps extending the same Dot from another DotFolder
+fn qualify_extends(dot_path: &Path, dotfolder: &str) -> Result<()> {
+    let path = dot_path.join(".dothub");
+
+    if !path.is_file() {
+        return Ok(());
+    }
+
+    let contents = fs::read_to_string(&path)
+        .with_context(|| format!("Couldn't read '{}'.", path.display()))?;
+    let mut document: DocumentMut = contents
+        .parse()
+        .with_context(|| format!("'{}' couldn't be parsed.", path.display()))?;
+
+    let qualify = |table: &mut dyn TableLike| {
+        let value = match table
+            .get_mut("extends")
+            .and_then(|extends| extends.as_value_mut())
+        {
+            Some(value) => value,
+            None => return false,
+        };
+
+        let new = match value.as_str() {
+            Some(extends) if !extends.contains('/') => format!("{}/{}", dotfolder, extends),
+            _ => return false,
+        };
+
+        let decor = value.decor().clone();
+        *value = new.into();
+        *value.decor_mut() = decor;
+
+        true
+    };
+
+    let mut changed = qualify(document.as_table_mut());
+
+    if let Some(overrides) = document
+        .get_mut("overrides")
+        .and_then(|overrides| overrides.as_array_of_tables_mut())
+    {
+        for o in overrides.iter_mut() {
+            changed |= qualify(o);
+        }
+    }
+
+    if changed {
+        fs::write(&path, document.to_string())
+            .with_context(|| format!("Couldn't write '{}'.", path.display()))?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,201 @@
+use std::{
+    collections::BTreeMap,
+    env,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use clap::ArgMatches;
+
+use crate::{dot_files, dot_is_active, dot_reload, layer, resolve_config, settings};
+
+// 'dothub edit', opens the editor on a Dot, a file of it or a DotFolder, works on a hub that
+// can't be loaded so a broken .dothub can be fixed
+pub fn edit(matches: &ArgMatches, folder_path: &Path) -> Result<()> {
+    let location = matches.get_one::<String>("location").unwrap();
+
+    let (dotfolder, dot) = match location.split_once('/') {
+        Some((dotfolder, dot)) if !dot.is_empty() => (dotfolder, Some(dot)),
+        _ => (location.trim_end_matches('/'), None),
+    };
+
+    let dotfolder_path = folder_path.join(dotfolder);
+
+    if dotfolder.is_empty() || dotfolder.starts_with('.') || !dotfolder_path.is_dir() {
+        bail!("DotFolder '{}' doesn't exist.", dotfolder);
+    }
+
+    let dot_path = match dot {
+        Some(dot) => dotfolder_path.join(dot),
+        None => {
+            if matches.contains_id("file") {
+                bail!("You have to specify the Dot to edit a file of, example 'waybar/neon'.");
+            }
+
+            return open(&dotfolder_path);
+        }
+    };
+
+    if dot.is_some_and(|dot| dot.starts_with('.')) || !dot_path.is_dir() {
+        bail!("Dot '{}' doesn't exist.", location);
+    }
+
+    // only a Dot that is set can be reloaded
+    let active = matches.get_flag("reload")
+        && resolve_config(&dotfolder_path, &dot_path)
+            .is_ok_and(|config| dot_is_active(&config, &dot_path));
+
+    match matches.get_one::<String>("file") {
+        Some(query) => open(&find_file(&dot_path, query)?)?,
+        None => open(&dot_path)?,
+    }
+
+    if !active {
+        return Ok(());
+    }
+
+    // catches a broken .dothub before anything is run
+    let config = resolve_config(&dotfolder_path, &dot_path)?;
+    layer::materialize(&config, &dot_path)?;
+    dot_reload(&config)?;
+
+    println!("Reloaded '{}'.", location);
+
+    Ok(())
+}
+
+// $VISUAL, $EDITOR, 'editor' of the settings, or vi
+fn editor() -> String {
+    ["VISUAL", "EDITOR"]
+        .iter()
+        .filter_map(|name| env::var(name).ok())
+        .find(|editor| !editor.is_empty())
+        .or_else(|| settings::get().editor.clone())
+        .unwrap_or_else(|| "vi".to_owned())
+}
+
+// the editor can have arguments of its own, 'code --wait' for example
+fn open(path: &Path) -> Result<()> {
+    let quoted = format!("'{}'", path.to_string_lossy().replace('\'', r"'\''"));
+    let editor = editor();
+
+    let status = settings::shell_command(&format!("{} {}", editor, quoted))
+        .status()
+        .with_context(|| format!("Couldn't run '{}'.", editor))?;
+
+    if !status.success() {
+        bail!("'{}' exited with {}.", editor, status);
+    }
+
+    Ok(())
+}
+
+// the file of the Dot that fits the query best: the exact path, the file name, a part of the
+// path, or the letters of the query in order. The files of the Dots it extends count too, they
+// are what gets deployed where the Dot doesn't have them
+fn find_file(dot_path: &Path, query: &str) -> Result<PathBuf> {
+    // a broken 'extends' still leaves the Dot's own files to edit
+    let layers = layer::layers(dot_path).unwrap_or_else(|_| vec![dot_path.to_path_buf()]);
+
+    // by their path in the merged Dot, with the layer they come from
+    let mut files = BTreeMap::new();
+
+    for layer in layers.iter().rev() {
+        for file in dot_files(layer)? {
+            // only the Dot's own .dothub is worth editing
+            if layer != dot_path && file == Path::new(".dothub") {
+                continue;
+            }
+
+            files.entry(file).or_insert(layer);
+        }
+    }
+
+    let query = query.to_lowercase();
+
+    let best = files
+        .iter()
+        .filter_map(|(file, layer)| {
+            let path = file.to_string_lossy().to_lowercase();
+            let name = file.file_name()?.to_string_lossy().to_lowercase();
+
+            let rank = if path == query {
+                0
+            } else if name == query {
+                1
+            } else if path.contains(&query) {
+                2
+            } else if is_subsequence(&query, &path) {
+                3
+            } else {
+                return None;
+            };
+
+            Some(((rank, path.len()), (file, layer)))
+        })
+        .min_by_key(|(rank, _)| *rank);
+
+    let (file, layer) = match best {
+        Some((_, found)) => found,
+        None => bail!(
+            "No file of '{}' matches '{}'.",
+            dot_path.file_name().unwrap().to_string_lossy(),
+            query
+        ),
+    };
+
+    if *layer != dot_path {
+        println!(
+            "'{}' comes from '{}', through 'extends'.",
+            file.display(),
+            dot_name(layer)
+        );
+    }
+
+    Ok(layer.join(file))
+}
+
+// 'DotFolder/Dot'
+fn dot_name(dot_path: &Path) -> String {
+    let dotfolder = dot_path
+        .parent()
+        .and_then(Path::file_name)
+        .unwrap_or_default();
+    let dot = dot_path.file_name().unwrap_or_default();
+
+    format!("{}/{}", dotfolder.to_string_lossy(), dot.to_string_lossy())
+}
+
+fn is_subsequence(query: &str, text: &str) -> bool {
+    let mut text = text.chars();
+
+    query.chars().all(|c| text.any(|t| t == c))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{fs, process};
+
+    #[test]
+    fn files_of_extended_dots_are_found() {
+        let dotfolder = env::temp_dir().join(format!("dothub-edit-{}", process::id()));
+        let (base, neon) = (dotfolder.join("base"), dotfolder.join("neon"));
+
+        fs::create_dir_all(&base).unwrap();
+        fs::create_dir_all(&neon).unwrap();
+        fs::write(base.join("style.css"), "").unwrap();
+        fs::write(base.join("config"), "").unwrap();
+        fs::write(base.join(".dothub"), "").unwrap();
+        fs::write(neon.join("config"), "").unwrap();
+        fs::write(neon.join(".dothub"), "extends = 'base'").unwrap();
+
+        let found = ["style", "config", ".dothub"].map(|query| find_file(&neon, query).unwrap());
+
+        fs::remove_dir_all(&dotfolder).unwrap();
+
+        assert_eq!(found[0], base.join("style.css"));
+        assert_eq!(found[1], neon.join("config"));
+        assert_eq!(found[2], neon.join(".dothub"));
+    }
+}
@@ -0,0 +1,736 @@
+use std::{
+    collections::BTreeMap,
+    env,
+    ffi::OsString,
+    fs,
+    io::{self, IsTerminal, Write},
+    os::unix::fs::symlink,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+use chrono::Local;
+use clap::ArgMatches;
+
+use crate::new::check_name;
+
+// what to do with a Dot that already exists in the .dothub
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Existing {
+    Ask,
+    Replace,
+    Skip,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct GetOptions {
+    existing: Existing,
+    interactive: bool,
+    adopt: bool,
+}
+
+pub fn dot_get(matches: &ArgMatches, folder_path: &Path) -> Result<()> {
+    if let Some(location) = matches.get_one::<String>("restore") {
+        return dot_restore(folder_path, location);
+    }
+
+    let existing = if matches.get_flag("force") {
+        Existing::Replace
+    } else if matches.get_flag("skip-existing") {
+        Existing::Skip
+    } else {
+        Existing::Ask
+    };
+
+    let options = GetOptions {
+        existing,
+        // only prompt when there is someone to answer
+        interactive: io::stdin().is_terminal(),
+        adopt: matches.get_flag("adopt"),
+    };
+    let target = matches.get_one::<String>("to");
+
+    let mut to_get: Vec<(PathBuf, String)> = vec![];
+
+    if let Some(map) = matches.get_one::<String>("map") {
+        to_get.extend(process_map(Path::new(map), folder_path)?);
+    }
+
+    let hub_path = folder_path.canonicalize()?;
+
+    // check if all paths given are valid
+    let paths: Vec<&Path> = matches
+        .get_many::<String>("paths")
+        .unwrap_or_default()
+        .filter_map(|p| {
+            let path = Path::new(p);
+            if !path.exists() {
+                println!("'{}' doesn't exist!", p);
+                None
+            } else if links_into_hub(path, &hub_path) {
+                println!("'{}' is already a link into your .dothub !", p);
+                None
+            } else {
+                Some(path)
+            }
+        })
+        .collect();
+
+    match target {
+        Some(location) => {
+            check_location(location)?;
+
+            for p in paths {
+                to_get.push((p.to_path_buf(), location.clone()));
+            }
+        }
+        None if paths.is_empty() => {}
+        None if options.interactive => {
+            println!(
+                "For every path, input it's location in your .dothub. Example: 'polybar/red_one'.
+Nonexsitent folders are gonna be created.
+Existent 'Dots' are gonna be replaced, the old ones are kept for 'get --restore'.\n"
+            );
+
+            // for each path arg, input it's .dothub location
+            for p in paths {
+                let location = loop {
+                    let location = input(&format!("'{}': ", p.display()));
+
+                    match check_location(&location) {
+                        Ok(_) => break location,
+                        Err(e) => println!("{}", e),
+                    }
+                };
+
+                to_get.push((p.to_path_buf(), location));
+            }
+        }
+        None => bail!("Where should the paths go? Use '--to DotFolder/Dot' or '--map file.toml'."),
+    }
+
+    if options.adopt {
+        for (path, location) in to_get {
+            adopt_one(folder_path, &path, &location, options)?;
+        }
+
+        return Ok(());
+    }
+
+    // every Dot is filled once, with all of its paths
+    let mut by_location: Vec<(String, Vec<PathBuf>)> = vec![];
+
+    for (path, location) in to_get {
+        match by_location.iter_mut().find(|(l, _)| *l == location) {
+            Some((_, paths)) => paths.push(path),
+            None => by_location.push((location, vec![path])),
+        }
+    }
+
+    for (location, paths) in by_location {
+        get_one(folder_path, &paths, &location, options)?;
+    }
+
+    Ok(())
+}
+
+// a single folder becomes the Dot, anything else is put into it by its name
+fn get_one(
+    folder_path: &Path,
+    paths: &[PathBuf],
+    location: &str,
+    options: GetOptions,
+) -> Result<()> {
+    let final_destination = folder_path.join(location);
+
+    if paths.len() > 1 {
+        let mut names: Vec<_> = paths.iter().map(|p| p.file_name().unwrap()).collect();
+        names.sort();
+
+        if let Some(name) = names.windows(2).find(|pair| pair[0] == pair[1]) {
+            bail!(
+                "More than one path named '{}' would go into '{}'.",
+                name[0].to_string_lossy(),
+                location
+            );
+        }
+    }
+
+    // if the destination in .dothub already exists, ask if it should be overwritten
+    if final_destination.exists() && !should_replace(location, options)? {
+        println!("Skipping '{}'.", location);
+        return Ok(());
+    }
+
+    let mut skipped = vec![];
+
+    let replaced = stage_dot(&final_destination, |staged| {
+        for path in paths {
+            if paths.len() == 1 && path.is_dir() {
+                copy_tree(path, staged, &mut skipped)
+                    .context("Couldn't copy dot folder over to your .dothub .")?;
+            } else {
+                copy_tree(path, &staged.join(path.file_name().unwrap()), &mut skipped)
+                    .context("Couldn't copy dot file over to your .dothub .")?;
+            }
+        }
+
+        Ok(())
+    })?;
+
+    print_skipped(&skipped);
+    print_replaced(location, replaced);
+
+    Ok(())
+}
+
+// fills a hidden sibling of the Dot first, so a failed copy never costs the old Dot,
+// the replaced Dot is kept next to it for 'get --restore'
+fn stage_dot(dot_path: &Path, fill: impl FnOnce(&Path) -> Result<()>) -> Result<Option<PathBuf>> {
+    let mut staged_name = OsString::from(".");
+    staged_name.push(dot_path.file_name().unwrap());
+    staged_name.push(".dothub-new");
+    let staged = dot_path.with_file_name(staged_name);
+
+    // left over from a 'get' that didn't finish
+    if staged.exists() {
+        fs::remove_dir_all(&staged).context("Couldn't remove an unfinished Dot.")?;
+    }
+
+    fs::create_dir_all(&staged).context("Couldn't create a new 'Dot' in your .dothub .")?;
+
+    if let Err(e) = fill(&staged) {
+        let _ = fs::remove_dir_all(&staged);
+        return Err(e);
+    }
+
+    if !dot_path.exists() {
+        fs::rename(&staged, dot_path).context("Couldn't move the new Dot in place.")?;
+        return Ok(None);
+    }
+
+    let backup = backup_path(dot_path);
+    swap_into_place(&staged, dot_path, &backup)?;
+
+    Ok(Some(backup))
+}
+
+// puts 'new' at 'path', and whatever was at 'path' at 'old'
+pub fn swap_into_place(new: &Path, path: &Path, old: &Path) -> Result<()> {
+    if exchange(new, path).is_ok() {
+        fs::rename(new, old).context("Couldn't keep the replaced Dot.")?;
+    } else {
+        fs::rename(path, old).context("Couldn't keep the replaced Dot.")?;
+        fs::rename(new, path).context("Couldn't move the new Dot in place.")?;
+    }
+
+    Ok(())
+}
+
+// copies a file or a folder the way it is: permissions, modification times and symlinks,
+// sockets, fifos and devices can't be copied, they are added to 'skipped'
+pub fn copy_tree(from: &Path, to: &Path, skipped: &mut Vec<PathBuf>) -> Result<()> {
+    let root = from.canonicalize().unwrap_or_else(|_| from.to_path_buf());
+    copy_entry(from, to, &root, 0, skipped)
+}
+
+fn copy_entry(
+    path: &Path,
+    destination: &Path,
+    root: &Path,
+    depth: usize,
+    skipped: &mut Vec<PathBuf>,
+) -> Result<()> {
+    let meta = fs::symlink_metadata(path)
+        .with_context(|| format!("Couldn't read '{}'.", path.display()))?;
+    let file_type = meta.file_type();
+
+    if file_type.is_symlink() {
+        let mut target = fs::read_link(path)?;
+
+        // absolute links into the copied folder are made relative, so they point into the copy
+        if let Ok(rest) = target.strip_prefix(root) {
+            if target.is_absolute() && depth > 0 {
+                target = std::iter::repeat_n(Path::new(".."), depth - 1)
+                    .collect::<PathBuf>()
+                    .join(rest);
+            }
+        }
+
+        remove_file_or_link(destination)?;
+        symlink(&target, destination)
+            .with_context(|| format!("Couldn't copy the symlink '{}'.", path.display()))?;
+
+        return Ok(());
+    }
+
+    if file_type.is_dir() {
+        fs::create_dir_all(destination)
+            .with_context(|| format!("Couldn't create '{}'.", destination.display()))?;
+
+        let mut entries = fs::read_dir(path)
+            .with_context(|| format!("Couldn't read '{}'.", path.display()))?
+            .map(|entry| entry.map(|entry| entry.file_name()))
+            .collect::<Result<Vec<_>, _>>()?;
+        entries.sort();
+
+        for name in entries {
+            copy_entry(
+                &path.join(&name),
+                &destination.join(&name),
+                root,
+                depth + 1,
+                skipped,
+            )?;
+        }
+
+        fs::set_permissions(destination, meta.permissions())?;
+    } else if file_type.is_file() {
+        // don't write through a symlink that is already there
+        remove_file_or_link(destination)?;
+
+        // permissions are copied as well
+        fs::copy(path, destination)
+            .with_context(|| format!("Couldn't copy '{}'.", path.display()))?;
+    } else {
+        skipped.push(path.to_path_buf());
+        return Ok(());
+    }
+
+    // after the folder's content, creating it changes the time again
+    fs::File::open(destination)
+        .and_then(|file| file.set_modified(meta.modified()?))
+        .with_context(|| {
+            format!(
+                "Couldn't set the modification time of '{}'.",
+                destination.display()
+            )
+        })?;
+
+    Ok(())
+}
+
+fn remove_file_or_link(path: &Path) -> Result<()> {
+    match fs::symlink_metadata(path) {
+        Ok(meta) if !meta.is_dir() => {
+            fs::remove_file(path).with_context(|| format!("Couldn't replace '{}'.", path.display()))
+        }
+        _ => Ok(()),
+    }
+}
+
+pub fn print_skipped(skipped: &[PathBuf]) {
+    for path in skipped {
+        println!(
+            "Skipped '{}', sockets, fifos and devices can't be copied.",
+            path.display()
+        );
+    }
+}
+
+// '.nord.replaced-20230105-142010' next to 'nord'
+fn backup_path(dot_path: &Path) -> PathBuf {
+    let prefix = backup_prefix(dot_path);
+    let timestamp = Local::now().format("%Y%m%d-%H%M%S");
+
+    let mut backup = dot_path.with_file_name(format!("{}{}", prefix, timestamp));
+    let mut n = 1;
+
+    while backup.exists() {
+        backup = dot_path.with_file_name(format!("{}{}-{}", prefix, timestamp, n));
+        n += 1;
+    }
+
+    backup
+}
+
+fn backup_prefix(dot_path: &Path) -> String {
+    format!(
+        ".{}.replaced-",
+        dot_path.file_name().unwrap().to_string_lossy()
+    )
+}
+
+fn print_replaced(location: &str, replaced: Option<PathBuf>) {
+    if let Some(backup) = replaced {
+        println!(
+            "The old '{}' is kept as '{}', 'dothub get --restore {}' brings it back.",
+            location,
+            backup.file_name().unwrap().to_string_lossy(),
+            location
+        );
+    }
+}
+
+// swaps the Dot with the last one 'get' replaced, the current one is kept as well
+pub fn dot_restore(folder_path: &Path, location: &str) -> Result<()> {
+    check_location(location)?;
+
+    let dot_path = folder_path.join(location);
+    let prefix = backup_prefix(&dot_path);
+
+    let mut backups: Vec<PathBuf> = fs::read_dir(dot_path.parent().unwrap())
+        .with_context(|| format!("There is no replaced '{}' to restore.", location))?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| {
+            path.file_name()
+                .is_some_and(|name| name.to_string_lossy().starts_with(&prefix))
+        })
+        .collect();
+
+    backups.sort();
+
+    let latest = backups
+        .pop()
+        .with_context(|| format!("There is no replaced '{}' to restore.", location))?;
+
+    if dot_path.exists() {
+        let backup = backup_path(&dot_path);
+        swap_into_place(&latest, &dot_path, &backup)?;
+
+        println!(
+            "Restored '{}', the current one is kept as '{}'.",
+            location,
+            backup.file_name().unwrap().to_string_lossy()
+        );
+    } else {
+        fs::rename(&latest, &dot_path).context("Couldn't restore the Dot.")?;
+        println!("Restored '{}'.", location);
+    }
+
+    Ok(())
+}
+
+// moves the path into the .dothub and links it back, so it's managed right away
+fn adopt_one(folder_path: &Path, path: &Path, location: &str, options: GetOptions) -> Result<()> {
+    let meta = fs::symlink_metadata(path)
+        .with_context(|| format!("Couldn't read '{}'.", path.display()))?;
+
+    if meta.file_type().is_symlink() {
+        bail!(
+            "'{}' is a symlink, only real files and folders can be adopted.",
+            path.display()
+        );
+    }
+
+    let path = path
+        .canonicalize()
+        .with_context(|| format!("Couldn't read '{}'.", path.display()))?;
+
+    let hub_path = folder_path.canonicalize()?;
+
+    if path.starts_with(&hub_path) {
+        bail!("'{}' is already inside your .dothub .", path.display());
+    } else if hub_path.starts_with(&path) {
+        bail!("'{}' contains your .dothub .", path.display());
+    }
+
+    let (dotfolder_name, dot_name) = location.split_once('/').unwrap();
+    let dotfolder_path = folder_path.join(dotfolder_name);
+    let dot_path = dotfolder_path.join(dot_name);
+
+    // a folder becomes the whole Dot, a file is one of the entries inside of a bigger folder
+    let whole = path.is_dir();
+    let (destination, in_dot) = if whole {
+        (path.clone(), dot_path.clone())
+    } else {
+        (
+            path.parent().context("Can't adopt '/'.")?.to_path_buf(),
+            dot_path.join(path.file_name().unwrap()),
+        )
+    };
+
+    check_dotfolder_config(&dotfolder_path, &destination, !whole)?;
+
+    if in_dot.exists() && !should_replace(location, options)? {
+        println!("Skipping '{}'.", location);
+        return Ok(());
+    }
+
+    let mut skipped = vec![];
+
+    if whole {
+        let replaced = stage_dot(&in_dot, |staged| {
+            copy_tree(&path, staged, &mut skipped)
+                .context("Couldn't copy dot folder over to your .dothub .")
+        })?;
+
+        print_replaced(location, replaced);
+    } else {
+        fs::create_dir_all(&dot_path).context("Couldn't create a new 'Dot' in your .dothub .")?;
+        copy_tree(&path, &in_dot, &mut skipped)
+            .context("Couldn't copy dot file over to your .dothub .")?;
+    }
+
+    // they would be gone once the original is replaced with the symlink
+    if !skipped.is_empty() {
+        print_skipped(&skipped);
+        println!(
+            "'{}' was copied to '{}', but isn't replaced by a symlink. Move them out of it and run 'dothub set {}'.",
+            path.display(),
+            location,
+            location
+        );
+        return Ok(());
+    }
+
+    let config_path = dotfolder_path.join(".dothub");
+
+    if !config_path.exists() {
+        let mut config = format!(
+            "# written by 'dothub get --adopt'\ndestination = '{}'\n",
+            contract_home(&destination)
+        );
+
+        if !whole {
+            config += "find_and_replace = true\n";
+        }
+
+        fs::write(&config_path, config).context("Couldn't write the DotFolder's .dothub .")?;
+    }
+
+    replace_with_symlink(&path, &in_dot)?;
+
+    println!("Adopted '{}' as '{}'.", path.display(), location);
+
+    Ok(())
+}
+
+// an existing DotFolder config has to link the adopted path back to where it was
+fn check_dotfolder_config(
+    dotfolder_path: &Path,
+    destination: &Path,
+    find_and_replace: bool,
+) -> Result<()> {
+    if !dotfolder_path.join(".dothub").exists() {
+        return Ok(());
+    }
+
+    let config = match crate::process_dotfolder(dotfolder_path)?.resolved_config() {
+        Some(config) => config,
+        None => return Ok(()),
+    };
+
+    let (expected, got) = (
+        (destination, find_and_replace),
+        (
+            Path::new(config.destination.as_deref().unwrap_or_default()),
+            config.find_and_replace.unwrap_or(false),
+        ),
+    );
+
+    if expected != got {
+        bail!(
+            "The DotFolder's .dothub links to '{}'{}, adopting needs '{}'{}.",
+            got.0.display(),
+            if got.1 { " with find_and_replace" } else { "" },
+            expected.0.display(),
+            if expected.1 {
+                " with find_and_replace"
+            } else {
+                ""
+            },
+        );
+    }
+
+    Ok(())
+}
+
+// puts a symlink where 'path' is, without a moment where 'path' doesn't exist
+fn replace_with_symlink(path: &Path, target: &Path) -> Result<()> {
+    let mut tmp_name = path.file_name().unwrap().to_owned();
+    tmp_name.push(".dothub-tmp");
+    let tmp = path.with_file_name(tmp_name);
+
+    let _ = fs::remove_file(&tmp);
+    symlink(target, &tmp).context("Couldn't create a symlink.")?;
+
+    if !path.is_dir() {
+        // renaming over a file replaces it atomically
+        return fs::rename(&tmp, path).context("Couldn't replace the original with a symlink.");
+    }
+
+    // a folder can't be renamed over, but it can be swapped
+    if exchange(&tmp, path).is_err() {
+        let mut old_name = path.file_name().unwrap().to_owned();
+        old_name.push(".dothub-old");
+        let old = path.with_file_name(old_name);
+
+        fs::rename(path, &old).context("Couldn't move the original away.")?;
+        fs::rename(&tmp, path).context("Couldn't replace the original with a symlink.")?;
+        return fs::remove_dir_all(old).context("Couldn't remove the original.");
+    }
+
+    // 'tmp' is now the original folder
+    fs::remove_dir_all(tmp).context("Couldn't remove the original.")
+}
+
+#[cfg(target_os = "linux")]
+fn exchange(a: &Path, b: &Path) -> io::Result<()> {
+    use std::{ffi::CString, os::unix::ffi::OsStrExt};
+
+    let a = CString::new(a.as_os_str().as_bytes())?;
+    let b = CString::new(b.as_os_str().as_bytes())?;
+
+    let result = unsafe {
+        libc::renameat2(
+            libc::AT_FDCWD,
+            a.as_ptr(),
+            libc::AT_FDCWD,
+            b.as_ptr(),
+            libc::RENAME_EXCHANGE,
+        )
+    };
+
+    match result {
+        0 => Ok(()),
+        _ => Err(io::Error::last_os_error()),
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn exchange(_a: &Path, _b: &Path) -> io::Result<()> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+// a leading '~' of a path in a .dothub is $HOME, '~user' isn't supported
+pub fn expand_home(path: &str) -> String {
+    match (path.strip_prefix('~'), env::var("HOME")) {
+        (Some(rest), Ok(home)) if rest.is_empty() || rest.starts_with('/') => home + rest,
+        _ => path.to_owned(),
+    }
+}
+
+// paths in .dothub files can start with '~'
+pub fn contract_home(path: &Path) -> String {
+    match env::var("HOME") {
+        Ok(home) => match path.strip_prefix(&home) {
+            Ok(rest) if rest.as_os_str().is_empty() => "~".to_owned(),
+            Ok(rest) => format!("~/{}", rest.display()),
+            Err(_) => path.display().to_string(),
+        },
+        Err(_) => path.display().to_string(),
+    }
+}
+
+fn should_replace(location: &str, options: GetOptions) -> Result<bool> {
+    match options.existing {
+        Existing::Replace => Ok(true),
+        Existing::Skip => Ok(false),
+        Existing::Ask if options.interactive => loop {
+            let user_choice = input(&format!(
+                "'{}' already exists, do you want to replace it? [y/n]: ",
+                location
+            ));
+
+            match user_choice.to_lowercase().chars().next() {
+                Some('y') => break Ok(true),
+                Some('n') => break Ok(false),
+                _ => {}
+            }
+        },
+        Existing::Ask => bail!(
+            "'{}' already exists, use '--force' to replace it or '--skip-existing' to keep it.",
+            location
+        ),
+    }
+}
+
+// a path that 'set' linked, getting it would copy the Dot into itself
+fn links_into_hub(path: &Path, hub_path: &Path) -> bool {
+    let is_symlink = fs::symlink_metadata(path).is_ok_and(|meta| meta.file_type().is_symlink());
+
+    is_symlink
+        && path
+            .canonicalize()
+            .is_ok_and(|target| target.starts_with(hub_path))
+}
+
+// 'DotFolder/Dot', both parts have to be there
+// the same names 'new' accepts, so nothing ends up outside of the hub ('../x') or hidden
+fn check_location(location: &str) -> Result<()> {
+    match location.split_once('/') {
+        Some((_, "")) => bail!("You have to input the 'Dot'."),
+        Some((df, d)) => {
+            check_name(df)?;
+            check_name(d)
+        }
+        None => bail!("The format is 'DotFolder/Dot', got '{}'.", location),
+    }
+}
+
+// a TOML file of '"path" = "DotFolder/Dot"', for getting many paths at once
+fn process_map(map_path: &Path, folder_path: &Path) -> Result<Vec<(PathBuf, String)>> {
+    let user_home = env::var("HOME").context("No $HOME set!")?;
+
+    let map_file = fs::read_to_string(map_path)
+        .with_context(|| format!("Couldn't read '{}'.", map_path.display()))?;
+
+    let map: BTreeMap<String, String> = toml::from_str(&map_file)
+        .with_context(|| format!("'{}' couldn't be parsed.", map_path.display()))?;
+
+    let mut to_get = vec![];
+
+    for (path, location) in map {
+        check_location(&location)?;
+
+        let path = match path.strip_prefix('~') {
+            Some(rest) => PathBuf::from(format!("{}{}", user_home, rest)),
+            None => PathBuf::from(path),
+        };
+
+        if !path.exists() {
+            println!("'{}' doesn't exist!", path.display());
+            continue;
+        }
+
+        if links_into_hub(&path, &folder_path.canonicalize()?) {
+            println!("'{}' is already a link into your .dothub !", path.display());
+            continue;
+        }
+
+        to_get.push((path, location));
+    }
+
+    Ok(to_get)
+}
+
+// helper function, i am lazy, I miss Python
+fn input(msg: &str) -> String {
+    println!("{msg}");
+
+    let mut buf = "".to_string();
+
+    io::stdout()
+        .flush()
+        .expect("Flush broken, try a different toilet.");
+    io::stdin()
+        .read_line(&mut buf)
+        .expect("Reading from stdin failed.");
+
+    // trim the leading '\n'
+    buf.trim().to_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locations_stay_inside_of_the_hub() {
+        assert!(check_location("waybar/neon").is_ok());
+
+        for location in [
+            "../x",
+            "./x",
+            "waybar/..",
+            "waybar/.",
+            "/x",
+            "a/b/c",
+            "waybar/",
+            "waybar",
+        ] {
+            assert!(check_location(location).is_err(), "{}", location);
+        }
+    }
+}
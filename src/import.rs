@@ -0,0 +1,914 @@
+use std::{
+    collections::{BTreeMap, HashSet},
+    env, fs,
+    os::unix::fs::{symlink, PermissionsExt},
+    path::{Component, Path, PathBuf},
+    process,
+};
+
+use anyhow::{bail, Context, Result};
+use clap::ArgMatches;
+
+use crate::get::contract_home;
+
+// folders shared by many programs, they are never linked as a whole
+const CONTAINERS: [&str; 4] = [".config", ".local", ".local/share", ".local/state"];
+
+// a file to import, 'target' is relative to where it's deployed (usually $HOME)
+#[derive(Debug)]
+struct Entry {
+    target: PathBuf,
+    source: Source,
+    mode: Option<u32>,
+}
+
+#[derive(Debug)]
+enum Source {
+    File(PathBuf),
+    Symlink(PathBuf),
+}
+
+// one DotFolder with a single Dot, as it will be written
+#[derive(Debug)]
+struct Unit {
+    dotfolder: String,
+    destination: PathBuf,
+    find_and_replace: bool,
+    entries: Vec<Entry>,
+}
+
+pub fn import(matches: &ArgMatches, folder_path: &Path) -> Result<()> {
+    let user_home = PathBuf::from(env::var("HOME").context("No $HOME set!")?);
+
+    let (kind, kind_matches) = matches.subcommand().unwrap();
+    let source = Path::new(kind_matches.get_one::<String>("path").unwrap());
+    let dot_name = kind_matches.get_one::<String>("dot").unwrap();
+
+    if !source.exists() {
+        bail!("'{}' doesn't exist!", source.display());
+    }
+
+    let source = source.canonicalize()?;
+    let mut report: Vec<String> = vec![];
+
+    let (target, units) = match kind {
+        "stow" => {
+            // like stow, the target defaults to the parent of the stow directory
+            let target = match kind_matches.get_one::<String>("target") {
+                Some(target) => PathBuf::from(target),
+                None => source.parent().unwrap_or(&user_home).to_path_buf(),
+            };
+
+            let dotfiles = kind_matches.get_flag("dotfiles");
+
+            (target, plan_stow(&source, dotfiles, &mut report)?)
+        }
+        "chezmoi" => {
+            let target = kind_matches
+                .get_one::<String>("target")
+                .map_or(user_home.clone(), PathBuf::from);
+
+            let root = chezmoi_root(&source, &mut report)?;
+            let entries = read_chezmoi(&root, Path::new(""), false, &mut report)?;
+            let units = keep_tracked(&target, plan_home(entries), &mut report);
+
+            (target, units)
+        }
+        "bare-git" => {
+            let target = match kind_matches.get_one::<String>("target") {
+                Some(target) => PathBuf::from(target),
+                None => git_worktree(&source)?.unwrap_or(user_home.clone()),
+            };
+
+            let entries = read_bare_git(&source, &target, &mut report)?;
+            let units = keep_tracked(&target, plan_home(entries), &mut report);
+
+            (target, units)
+        }
+        _ => unreachable!(),
+    };
+
+    for unit in units {
+        match write_unit(folder_path, &target, dot_name, &unit) {
+            Ok(_) => println!(
+                "{}/{} -> {}",
+                unit.dotfolder,
+                dot_name,
+                contract_home(&target.join(&unit.destination))
+            ),
+            Err(e) => report.push(format!("{}: {:#}", unit.dotfolder, e)),
+        }
+    }
+
+    if !report.is_empty() {
+        println!("\nCouldn't convert:");
+        for line in report {
+            println!("  {}", line);
+        }
+    }
+
+    Ok(())
+}
+
+// every stow package becomes a DotFolder
+fn plan_stow(stow_dir: &Path, dotfiles: bool, report: &mut Vec<String>) -> Result<Vec<Unit>> {
+    let mut units = vec![];
+
+    for package in sorted_dir(stow_dir)? {
+        let name = file_name(&package);
+
+        if !package.is_dir() || name.starts_with('.') {
+            continue;
+        }
+
+        if package.join(".stow-local-ignore").exists() {
+            report.push(format!(
+                "{}: '.stow-local-ignore' isn't applied, check the imported files.",
+                name
+            ));
+        }
+
+        let mut entries = vec![];
+        read_stow(&package, Path::new(""), dotfiles, &mut entries)?;
+
+        if entries.is_empty() {
+            continue;
+        }
+
+        match plan_package(&name, entries) {
+            Ok(unit) => units.push(unit),
+            Err(e) => report.push(format!("{}: {:#}", name, e)),
+        }
+    }
+
+    Ok(units)
+}
+
+fn read_stow(path: &Path, relative: &Path, dotfiles: bool, entries: &mut Vec<Entry>) -> Result<()> {
+    for item in sorted_dir(path)? {
+        let mut name = file_name(&item);
+
+        // stow's default ignore list
+        if [".git", ".gitignore", ".gitmodules", ".stow-local-ignore"].contains(&name.as_str())
+            || name.ends_with('~')
+            || (relative.as_os_str().is_empty()
+                && (name.starts_with("README") || name.starts_with("LICENSE") || name == "COPYING"))
+        {
+            continue;
+        }
+
+        // 'stow --dotfiles'
+        if dotfiles {
+            if let Some(rest) = name.strip_prefix("dot-") {
+                name = format!(".{}", rest);
+            }
+        }
+
+        let target = relative.join(&name);
+        let meta = fs::symlink_metadata(&item)?;
+
+        if meta.file_type().is_symlink() {
+            entries.push(Entry {
+                target,
+                source: Source::Symlink(fs::read_link(&item)?),
+                mode: None,
+            });
+        } else if meta.is_dir() {
+            read_stow(&item, &target, dotfiles, entries)?;
+        } else if meta.is_file() {
+            entries.push(Entry {
+                target,
+                source: Source::File(item),
+                mode: Some(meta.permissions().mode()),
+            });
+        }
+    }
+
+    Ok(())
+}
+
+// a package is linked as one folder if it is one, otherwise its entries are linked one by one
+fn plan_package(name: &str, entries: Vec<Entry>) -> Result<Unit> {
+    let mut prefix = PathBuf::new();
+
+    loop {
+        let level = children(&entries, &prefix);
+
+        if level.len() == 1 {
+            let (child, is_dir) = level.iter().next().map(|(c, d)| (c.clone(), *d)).unwrap();
+            let child_path = prefix.join(&child);
+
+            if is_dir && is_container(&child_path) {
+                prefix = child_path;
+                continue;
+            }
+
+            if is_dir {
+                return Ok(Unit {
+                    dotfolder: name.to_owned(),
+                    entries: strip_entries(entries, &child_path),
+                    destination: child_path,
+                    find_and_replace: false,
+                });
+            }
+        }
+
+        if let Some((child, _)) = level
+            .iter()
+            .find(|(child, is_dir)| **is_dir && is_container(&prefix.join(child)))
+        {
+            bail!(
+                "mixes '{}' with other files, split it into more packages.",
+                prefix.join(child).display()
+            );
+        }
+
+        return Ok(Unit {
+            dotfolder: name.to_owned(),
+            entries: strip_entries(entries, &prefix),
+            destination: prefix,
+            find_and_replace: true,
+        });
+    }
+}
+
+// a whole home folder (chezmoi, bare git) is split into a DotFolder per program
+fn plan_home(entries: Vec<Entry>) -> Vec<Unit> {
+    let mut units = vec![];
+    let mut taken = HashSet::new();
+
+    plan_level(entries, PathBuf::new(), &mut units, &mut taken);
+
+    units
+}
+
+fn plan_level(
+    entries: Vec<Entry>,
+    prefix: PathBuf,
+    units: &mut Vec<Unit>,
+    taken: &mut HashSet<String>,
+) {
+    let mut groups: BTreeMap<String, Vec<Entry>> = BTreeMap::new();
+    let mut loose = vec![];
+
+    for entry in entries {
+        let rest = entry.target.strip_prefix(&prefix).unwrap().to_path_buf();
+        let mut components = rest.components();
+        let first = components
+            .next()
+            .unwrap()
+            .as_os_str()
+            .to_string_lossy()
+            .to_string();
+
+        if components.next().is_none() {
+            loose.push(entry);
+        } else {
+            groups.entry(first).or_default().push(entry);
+        }
+    }
+
+    for (child, group) in groups {
+        let child_path = prefix.join(&child);
+
+        if is_container(&child_path) {
+            plan_level(group, child_path, units, taken);
+        } else {
+            // chezmoi and git often track only some files of a folder, linking it as a whole
+            // would delete the others
+            let root = deepest_folder(&group);
+
+            units.push(Unit {
+                dotfolder: dotfolder_name(&child_path, taken),
+                entries: strip_entries(group, &root),
+                destination: root,
+                find_and_replace: true,
+            });
+        }
+    }
+
+    if !loose.is_empty() {
+        units.push(Unit {
+            dotfolder: dotfolder_name(&prefix, taken),
+            entries: strip_entries(loose, &prefix),
+            destination: prefix,
+            find_and_replace: true,
+        });
+    }
+}
+
+// the deepest folder all the entries are in
+fn deepest_folder(entries: &[Entry]) -> PathBuf {
+    let mut root = entries[0]
+        .target
+        .parent()
+        .unwrap_or(Path::new(""))
+        .to_path_buf();
+
+    for entry in entries {
+        while !entry.target.starts_with(&root) {
+            root.pop();
+        }
+    }
+
+    root
+}
+
+// units with folders that hold files that aren't imported are left out, 'set' replaces those
+// folders as a whole
+fn keep_tracked(target: &Path, units: Vec<Unit>, report: &mut Vec<String>) -> Vec<Unit> {
+    units
+        .into_iter()
+        .filter(|unit| {
+            let untracked = untracked(target, unit);
+
+            if untracked.is_empty() {
+                return true;
+            }
+
+            let shown: Vec<String> = untracked
+                .iter()
+                .take(3)
+                .map(|file| format!("'{}'", contract_home(&target.join(file))))
+                .collect();
+
+            report.push(format!(
+                "{}: 'set' would delete files that aren't imported: {}{}. Track them too, or add the rest by hand.",
+                unit.dotfolder,
+                shown.join(", "),
+                if untracked.len() > 3 { " and more" } else { "" }
+            ));
+
+            false
+        })
+        .collect()
+}
+
+// the files on disk in the folders a unit links as a whole that the unit doesn't have, relative
+// to 'target'
+fn untracked(target: &Path, unit: &Unit) -> Vec<PathBuf> {
+    let tracked: HashSet<&Path> = unit.entries.iter().map(|e| e.target.as_path()).collect();
+    let mut untracked = vec![];
+
+    for (child, is_dir) in children(&unit.entries, Path::new("")) {
+        let folder = target.join(&unit.destination).join(&child);
+
+        // a symlink is only replaced, not what it points to
+        let is_real_dir = fs::symlink_metadata(&folder).is_ok_and(|meta| meta.is_dir());
+
+        if !is_dir || !is_real_dir {
+            continue;
+        }
+
+        for file in crate::dot_files(&folder).unwrap_or_default() {
+            let file = Path::new(&child).join(file);
+
+            if !tracked.contains(file.as_path()) {
+                untracked.push(unit.destination.join(file));
+            }
+        }
+    }
+
+    untracked
+}
+
+fn dotfolder_name(path: &Path, taken: &mut HashSet<String>) -> String {
+    let name = file_name(path).trim_start_matches('.').to_owned();
+    let name = if name.is_empty() {
+        "home".to_owned()
+    } else {
+        name
+    };
+
+    // '~/.vim' and '~/.config/vim' can't both be 'vim'
+    let name = if taken.contains(&name) {
+        let parent = path.parent().map(file_name).unwrap_or_default();
+        let parent = match parent.trim_start_matches('.') {
+            "" => "home",
+            parent => parent,
+        };
+
+        format!("{}-{}", parent, name)
+    } else {
+        name
+    };
+
+    taken.insert(name.clone());
+    name
+}
+
+// '.chezmoiroot' moves the source state into a folder of the source directory
+fn chezmoi_root(source: &Path, report: &mut Vec<String>) -> Result<PathBuf> {
+    let file = source.join(".chezmoiroot");
+
+    if !file.is_file() {
+        return Ok(source.to_path_buf());
+    }
+
+    let relative = fs::read_to_string(&file).context("Couldn't read '.chezmoiroot'.")?;
+    let relative = relative.trim();
+    let root = source.join(relative);
+
+    if !root.is_dir() || !root.canonicalize()?.starts_with(source) {
+        bail!(
+            "'.chezmoiroot' points to '{}', which isn't a folder of '{}'.",
+            relative,
+            source.display()
+        );
+    }
+
+    report.push(format!(
+        "Imported from '{}', as '.chezmoiroot' says.",
+        relative
+    ));
+
+    Ok(root)
+}
+
+// 'private' folders make everything inside of them private too
+fn read_chezmoi(
+    path: &Path,
+    relative: &Path,
+    private: bool,
+    report: &mut Vec<String>,
+) -> Result<Vec<Entry>> {
+    let mut entries = vec![];
+
+    for item in sorted_dir(path)? {
+        let name = file_name(&item);
+
+        // chezmoi ignores everything starting with '.' in the source directory
+        if name.starts_with('.') {
+            if name.starts_with(".chezmoi") {
+                report.push(format!(
+                    "'{}' isn't applied, check the imported files.",
+                    relative.join(&name).display()
+                ));
+            }
+            continue;
+        }
+
+        let attrs = ChezmoiName::parse(&name);
+        let target = relative.join(&attrs.name);
+        let shown = relative.join(&name);
+
+        if let Some(reason) = attrs.unsupported() {
+            report.push(format!("'{}' {}", shown.display(), reason));
+            continue;
+        }
+
+        let meta = fs::symlink_metadata(&item)?;
+
+        if meta.is_dir() {
+            entries.extend(read_chezmoi(
+                &item,
+                &target,
+                private || attrs.private,
+                report,
+            )?);
+        } else if attrs.symlink {
+            let link = fs::read_to_string(&item)?;
+
+            entries.push(Entry {
+                target,
+                source: Source::Symlink(PathBuf::from(link.trim())),
+                mode: None,
+            });
+        } else {
+            let mut mode = if attrs.executable { 0o755 } else { 0o644 };
+
+            if private || attrs.private {
+                mode &= 0o700;
+            }
+            if attrs.readonly {
+                mode &= !0o222;
+            }
+
+            entries.push(Entry {
+                target,
+                source: Source::File(item),
+                mode: Some(mode),
+            });
+        }
+    }
+
+    Ok(entries)
+}
+
+// the attributes chezmoi keeps in file names, 'private_dot_ssh' -> '.ssh'
+#[derive(Debug, Default)]
+struct ChezmoiName {
+    name: String,
+    private: bool,
+    readonly: bool,
+    executable: bool,
+    symlink: bool,
+    template: bool,
+    encrypted: bool,
+    script: bool,
+    modify: bool,
+    remove: bool,
+}
+
+impl ChezmoiName {
+    fn parse(source_name: &str) -> ChezmoiName {
+        let mut attrs = ChezmoiName::default();
+        let mut name = source_name;
+
+        while let Some((prefix, rest)) = name.split_once('_') {
+            match prefix {
+                "private" => attrs.private = true,
+                "readonly" => attrs.readonly = true,
+                "executable" => attrs.executable = true,
+                "symlink" => attrs.symlink = true,
+                "encrypted" => attrs.encrypted = true,
+                "modify" => attrs.modify = true,
+                "remove" => attrs.remove = true,
+                "run" => attrs.script = true,
+                "empty" | "exact" | "create" | "once" | "onchange" | "before" | "after"
+                | "external" => {}
+                "literal" => {
+                    name = rest;
+                    break;
+                }
+                "dot" => {
+                    attrs.name = format!(".{}", rest);
+                    name = "";
+                    break;
+                }
+                _ => break,
+            }
+
+            name = rest;
+        }
+
+        if !name.is_empty() {
+            attrs.name = name.to_owned();
+        }
+
+        if let Some(stripped) = attrs.name.strip_suffix(".tmpl") {
+            attrs.template = true;
+            attrs.name = stripped.to_owned();
+        } else if let Some(stripped) = attrs.name.strip_suffix(".literal") {
+            attrs.name = stripped.to_owned();
+        }
+
+        attrs
+    }
+
+    fn unsupported(&self) -> Option<&'static str> {
+        if self.script {
+            Some("is a script, dothub doesn't run scripts.")
+        } else if self.encrypted {
+            Some("is encrypted, decrypt it and add it by hand.")
+        } else if self.template {
+            Some("is a template, add the rendered file by hand.")
+        } else if self.modify {
+            Some("is a modify script, dothub doesn't run scripts.")
+        } else if self.remove {
+            Some("only removes a file, there is nothing to import.")
+        } else {
+            None
+        }
+    }
+}
+
+fn git_worktree(git_dir: &Path) -> Result<Option<PathBuf>> {
+    let output = process::Command::new("git")
+        .arg("--git-dir")
+        .arg(git_dir)
+        .args(["config", "core.worktree"])
+        .output()
+        .context("Couldn't run git.")?;
+
+    let worktree = String::from_utf8_lossy(&output.stdout).trim().to_owned();
+
+    Ok(match worktree.is_empty() {
+        true => None,
+        false => Some(PathBuf::from(worktree)),
+    })
+}
+
+// the tracked files, as they are in the work tree
+fn read_bare_git(git_dir: &Path, work_tree: &Path, report: &mut Vec<String>) -> Result<Vec<Entry>> {
+    let output = process::Command::new("git")
+        .arg("--git-dir")
+        .arg(git_dir)
+        .arg("--work-tree")
+        .arg(work_tree)
+        .args(["ls-files", "-z"])
+        .output()
+        .context("Couldn't run git.")?;
+
+    if !output.status.success() {
+        bail!(
+            "git couldn't list the files of '{}': {}",
+            git_dir.display(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+
+    let mut entries = vec![];
+
+    for file in String::from_utf8_lossy(&output.stdout).split('\0') {
+        if file.is_empty() || file.starts_with("README") || file.starts_with("LICENSE") {
+            continue;
+        }
+
+        let target = PathBuf::from(file);
+        let path = work_tree.join(file);
+
+        match fs::symlink_metadata(&path) {
+            Ok(meta) if meta.file_type().is_symlink() => entries.push(Entry {
+                target,
+                source: Source::Symlink(fs::read_link(&path)?),
+                mode: None,
+            }),
+            Ok(meta) => entries.push(Entry {
+                target,
+                mode: Some(meta.permissions().mode()),
+                source: Source::File(path),
+            }),
+            Err(_) => report.push(format!("'{}' is tracked, but missing.", file)),
+        }
+    }
+
+    Ok(entries)
+}
+
+fn write_unit(folder_path: &Path, target: &Path, dot_name: &str, unit: &Unit) -> Result<()> {
+    let dotfolder_path = folder_path.join(&unit.dotfolder);
+    let dot_path = dotfolder_path.join(dot_name);
+    let destination = target.join(&unit.destination);
+    let config_path = dotfolder_path.join(".dothub");
+
+    if dot_path.exists() {
+        bail!("'{}/{}' already exists.", unit.dotfolder, dot_name);
+    }
+
+    if config_path.exists() {
+        let config = crate::process_dotfolder(&dotfolder_path)?.resolved_config();
+
+        let matches = config.is_some_and(|config| {
+            config.destination.as_deref().map(Path::new) == Some(destination.as_path())
+                && config.find_and_replace.unwrap_or(false) == unit.find_and_replace
+        });
+
+        if !matches {
+            bail!(
+                "the DotFolder already exists with a different 'destination' than '{}'.",
+                contract_home(&destination)
+            );
+        }
+    }
+
+    for entry in &unit.entries {
+        let path = dot_path.join(&entry.target);
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).context("Couldn't create a folder in the Dot.")?;
+        }
+
+        match &entry.source {
+            Source::File(source) => {
+                fs::copy(source, &path)
+                    .with_context(|| format!("Couldn't copy '{}'.", source.display()))?;
+            }
+            Source::Symlink(link) => {
+                symlink(link, &path).context("Couldn't create a symlink.")?;
+            }
+        }
+
+        if let Some(mode) = entry.mode {
+            fs::set_permissions(&path, fs::Permissions::from_mode(mode))?;
+        }
+    }
+
+    if !config_path.exists() {
+        let mut config = format!(
+            "# written by 'dothub import'\ndestination = '{}'\n",
+            contract_home(&destination)
+        );
+
+        if unit.find_and_replace {
+            config += "find_and_replace = true\n";
+        }
+
+        fs::write(&config_path, config).context("Couldn't write the DotFolder's .dothub .")?;
+    }
+
+    Ok(())
+}
+
+// the names directly under 'prefix', and if they are folders
+fn children(entries: &[Entry], prefix: &Path) -> BTreeMap<String, bool> {
+    let mut children = BTreeMap::new();
+
+    for entry in entries {
+        let mut rest = entry.target.strip_prefix(prefix).unwrap().components();
+
+        if let Some(Component::Normal(first)) = rest.next() {
+            let is_dir = rest.next().is_some();
+            *children
+                .entry(first.to_string_lossy().to_string())
+                .or_insert(false) |= is_dir;
+        }
+    }
+
+    children
+}
+
+fn strip_entries(entries: Vec<Entry>, prefix: &Path) -> Vec<Entry> {
+    entries
+        .into_iter()
+        .map(|entry| Entry {
+            target: entry.target.strip_prefix(prefix).unwrap().to_path_buf(),
+            ..entry
+        })
+        .collect()
+}
+
+fn is_container(path: &Path) -> bool {
+    CONTAINERS.iter().any(|c| Path::new(c) == path)
+}
+
+fn sorted_dir(path: &Path) -> Result<Vec<PathBuf>> {
+    let mut items = fs::read_dir(path)
+        .with_context(|| format!("Couldn't read '{}'.", path.display()))?
+        .map(|item| item.map(|item| item.path()))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    items.sort();
+
+    Ok(items)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entries(targets: &[&str]) -> Vec<Entry> {
+        targets
+            .iter()
+            .map(|target| Entry {
+                target: PathBuf::from(target),
+                source: Source::File(PathBuf::new()),
+                mode: None,
+            })
+            .collect()
+    }
+
+    fn targets(unit: &Unit) -> Vec<&str> {
+        unit.entries
+            .iter()
+            .map(|entry| entry.target.to_str().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn chezmoi_name_attributes() {
+        let attrs = ChezmoiName::parse("private_dot_ssh");
+        assert_eq!(attrs.name, ".ssh");
+        assert!(attrs.private);
+
+        let attrs = ChezmoiName::parse("executable_readonly_dot_local_bin");
+        assert_eq!(attrs.name, ".local_bin");
+        assert!(attrs.executable && attrs.readonly);
+
+        let attrs = ChezmoiName::parse("symlink_dot_vimrc");
+        assert_eq!(attrs.name, ".vimrc");
+        assert!(attrs.symlink);
+
+        let attrs = ChezmoiName::parse("dot_gitconfig.tmpl");
+        assert_eq!(attrs.name, ".gitconfig");
+        assert!(attrs.template);
+        assert!(attrs.unsupported().is_some());
+
+        assert_eq!(ChezmoiName::parse("literal_dot_x").name, "dot_x");
+        assert_eq!(ChezmoiName::parse("file.literal").name, "file");
+        assert_eq!(ChezmoiName::parse("create_once_config").name, "config");
+        assert_eq!(ChezmoiName::parse("my_file").name, "my_file");
+        assert!(ChezmoiName::parse("run_once_setup.sh").script);
+    }
+
+    #[test]
+    fn chezmoiroot_is_followed() {
+        let source = env::temp_dir().join(format!("dothub-chezmoiroot-{}", process::id()));
+        fs::create_dir_all(source.join("home")).unwrap();
+        fs::write(source.join(".chezmoiroot"), "home\n").unwrap();
+        fs::write(source.join("home/dot_zshrc"), "").unwrap();
+        fs::write(source.join("README.md"), "").unwrap();
+
+        let mut report = vec![];
+        let root = chezmoi_root(&source, &mut report).unwrap();
+        let entries = read_chezmoi(&root, Path::new(""), false, &mut report).unwrap();
+
+        fs::write(source.join(".chezmoiroot"), "..").unwrap();
+        let outside = chezmoi_root(&source, &mut vec![]);
+
+        fs::remove_dir_all(&source).unwrap();
+
+        assert_eq!(root, source.join("home"));
+        assert_eq!(report.len(), 1);
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].target, Path::new(".zshrc"));
+        assert!(outside.is_err());
+    }
+
+    #[test]
+    fn package_of_one_folder_is_linked_whole() {
+        let unit = plan_package(
+            "nvim",
+            entries(&[".config/nvim/init.lua", ".config/nvim/lua/a.lua"]),
+        )
+        .unwrap();
+
+        assert_eq!(unit.destination, Path::new(".config/nvim"));
+        assert!(!unit.find_and_replace);
+        assert_eq!(targets(&unit), ["init.lua", "lua/a.lua"]);
+    }
+
+    #[test]
+    fn package_of_loose_files_is_find_and_replace() {
+        let unit = plan_package("zsh", entries(&[".zshrc", ".zprofile"])).unwrap();
+
+        assert_eq!(unit.destination, Path::new(""));
+        assert!(unit.find_and_replace);
+        assert_eq!(targets(&unit), [".zshrc", ".zprofile"]);
+    }
+
+    #[test]
+    fn package_mixing_a_container_is_rejected() {
+        assert!(plan_package("mixed", entries(&[".zshrc", ".config/foo/bar"])).is_err());
+    }
+
+    #[test]
+    fn home_only_links_tracked_files() {
+        let units = plan_home(entries(&[".ssh/config"]));
+
+        assert_eq!(units.len(), 1);
+        assert_eq!(units[0].dotfolder, "ssh");
+        assert_eq!(units[0].destination, Path::new(".ssh"));
+        assert!(units[0].find_and_replace);
+        assert_eq!(targets(&units[0]), ["config"]);
+    }
+
+    #[test]
+    fn home_is_split_per_program() {
+        let units = plan_home(entries(&[
+            ".bashrc",
+            ".config/nvim/init.lua",
+            ".config/nvim/lua/a.lua",
+            ".config/kitty/themes/nord.conf",
+            ".vim/vimrc",
+            ".config/vim/vimrc",
+        ]));
+
+        let planned: Vec<(&str, &str, Vec<&str>)> = units
+            .iter()
+            .map(|unit| {
+                (
+                    unit.dotfolder.as_str(),
+                    unit.destination.to_str().unwrap(),
+                    targets(unit),
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            planned,
+            [
+                ("kitty", ".config/kitty/themes", vec!["nord.conf"]),
+                ("nvim", ".config/nvim", vec!["init.lua", "lua/a.lua"]),
+                ("vim", ".config/vim", vec!["vimrc"]),
+                ("home-vim", ".vim", vec!["vimrc"]),
+                ("home", "", vec![".bashrc"]),
+            ]
+        );
+        assert!(units.iter().all(|unit| unit.find_and_replace));
+    }
+
+    #[test]
+    fn untracked_files_are_found() {
+        let target = env::temp_dir().join(format!("dothub-import-{}", process::id()));
+        fs::create_dir_all(target.join(".config/nvim/lua")).unwrap();
+        fs::write(target.join(".config/nvim/lua/a.lua"), "").unwrap();
+        fs::write(target.join(".config/nvim/lua/local.lua"), "").unwrap();
+
+        let units = plan_home(entries(&[
+            ".config/nvim/init.lua",
+            ".config/nvim/lua/a.lua",
+        ]));
+        let untracked = untracked(&target, &units[0]);
+
+        fs::remove_dir_all(&target).unwrap();
+
+        assert_eq!(untracked, [PathBuf::from(".config/nvim/lua/local.lua")]);
+    }
+}
@@ -0,0 +1,227 @@
+use std::{
+    collections::BTreeMap,
+    ffi::OsString,
+    fs,
+    os::unix::fs::symlink,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
+
+use crate::{dot_files, get::swap_into_place, process_dot, remove_existing, DotConfig};
+
+// the Dots a Dot is made of through 'extends', the base first and the Dot itself last,
+// [[overrides]] can change 'extends' too
+pub fn layers(dot_path: &Path) -> Result<Vec<PathBuf>> {
+    let mut layers = vec![dot_path.to_path_buf()];
+    let mut current = dot_path.to_path_buf();
+
+    while let Some(extends) = process_dot(&current)?
+        .config
+        .and_then(|c| c.with_overrides().extends)
+    {
+        let dotfolder_path = current.parent().unwrap();
+
+        // 'base' is in the same DotFolder, 'OtherFolder/base' anywhere in the hub
+        let base = match extends.split_once('/') {
+            Some((dotfolder, dot)) => {
+                check_name(&extends, dotfolder)?;
+                check_name(&extends, dot)?;
+                dotfolder_path.parent().unwrap().join(dotfolder).join(dot)
+            }
+            None => {
+                check_name(&extends, &extends)?;
+                dotfolder_path.join(&extends)
+            }
+        };
+
+        if !base.is_dir() {
+            bail!(
+                "'{}' extends '{}', which doesn't exist.",
+                current.display(),
+                extends
+            );
+        }
+
+        if layers.contains(&base) {
+            bail!("'{}' ends up extending itself.", dot_path.display());
+        }
+
+        layers.push(base.clone());
+        current = base;
+    }
+
+    layers.reverse();
+
+    Ok(layers)
+}
+
+fn check_name(extends: &str, name: &str) -> Result<()> {
+    if name.is_empty() || name.starts_with('.') {
+        bail!(
+            "'{}' isn't a Dot, use 'Dot' or 'DotFolder/Dot' in 'extends'.",
+            extends
+        );
+    }
+
+    Ok(())
+}
+
+// '.nord.merged' next to 'nord', hidden so it isn't taken for a Dot
+pub fn merged_path(dot_path: &Path) -> PathBuf {
+    let mut name = OsString::from(".");
+    name.push(dot_path.file_name().unwrap());
+    name.push(".merged");
+
+    dot_path.with_file_name(name)
+}
+
+// what gets linked to the destination
+pub fn link_source(config: &DotConfig, dot_path: &Path) -> PathBuf {
+    match config.extends {
+        Some(_) => merged_path(dot_path),
+        None => dot_path.to_path_buf(),
+    }
+}
+
+// builds the merged tree of a Dot that extends another one, files of a Dot win over the ones
+// of the Dot it extends, returns the folder to link
+pub fn materialize(config: &DotConfig, dot_path: &Path) -> Result<PathBuf> {
+    if config.extends.is_none() {
+        return Ok(dot_path.to_path_buf());
+    }
+
+    let merged = merged_path(dot_path);
+
+    let mut new_name = merged.file_name().unwrap().to_owned();
+    new_name.push("-new");
+    let new = merged.with_file_name(new_name);
+
+    remove_existing(&new)?;
+    fs::create_dir_all(&new).context("Couldn't create the merged Dot.")?;
+
+    for layer in layers(dot_path)? {
+        merge_layer(&layer, &new, true)
+            .with_context(|| format!("Couldn't merge '{}'.", layer.display()))?;
+    }
+
+    // the links at the destination point at 'merged', so swap it instead of removing it first
+    if merged.exists() {
+        let mut old_name = merged.file_name().unwrap().to_owned();
+        old_name.push("-old");
+        let old = merged.with_file_name(old_name);
+
+        remove_existing(&old)?;
+        swap_into_place(&new, &merged, &old)?;
+        remove_existing(&old)?;
+    } else {
+        fs::rename(&new, &merged).context("Couldn't move the merged Dot in place.")?;
+    }
+
+    Ok(merged)
+}
+
+// folders are created, files are links to the layer they come from, so edits end up in it
+fn merge_layer(layer: &Path, merged: &Path, top: bool) -> Result<()> {
+    let mut entries = fs::read_dir(layer)?
+        .map(|entry| entry.map(|entry| entry.file_name()))
+        .collect::<Result<Vec<_>, _>>()?;
+    entries.sort();
+
+    for name in entries {
+        if top && name == ".dothub" {
+            continue;
+        }
+
+        let (path, destination) = (layer.join(&name), merged.join(&name));
+        let is_dir = fs::symlink_metadata(&path)?.is_dir();
+        let merged_dir = fs::symlink_metadata(&destination).is_ok_and(|meta| meta.is_dir());
+
+        if is_dir {
+            if !merged_dir {
+                remove_existing(&destination)?;
+                fs::create_dir(&destination)?;
+            }
+
+            merge_layer(&path, &destination, false)?;
+        } else {
+            remove_existing(&destination)?;
+            symlink(&path, &destination)?;
+        }
+    }
+
+    Ok(())
+}
+
+// the files the merged tree of a Dot has, by their path inside of it, with the file of the
+// layer they come from
+pub fn dot_view(dot_path: &Path) -> Result<BTreeMap<PathBuf, PathBuf>> {
+    let mut view = BTreeMap::new();
+
+    for layer in layers(dot_path)? {
+        for file in dot_files(&layer)? {
+            if file != Path::new(".dothub") {
+                view.insert(file.clone(), layer.join(file));
+            }
+        }
+    }
+
+    Ok(view)
+}
+
+// removes the merged tree of a Dot, if there is one
+pub fn remove_merged(dot_path: &Path) -> Result<()> {
+    remove_existing(&merged_path(dot_path))
+}
+
+// 'DotFolder/Dot' of the Dots that extend the given one
+pub fn extended_by(folder_path: &Path, dotfolder: &str, dot: &str) -> Result<Vec<String>> {
+    let dot_path = folder_path.join(dotfolder).join(dot);
+    let mut extending = vec![];
+
+    for (df, d) in all_dots(folder_path)? {
+        let path = folder_path.join(&df).join(&d);
+
+        // a broken Dot somewhere else shouldn't get in the way
+        let layers = match layers(&path) {
+            Ok(layers) => layers,
+            Err(_) => continue,
+        };
+
+        if path != dot_path && layers.contains(&dot_path) {
+            extending.push(format!("{}/{}", df, d));
+        }
+    }
+
+    Ok(extending)
+}
+
+pub fn all_dots(folder_path: &Path) -> Result<Vec<(String, String)>> {
+    let mut dots = vec![];
+
+    for dotfolder in fs::read_dir(folder_path).context("Couldn't read your .dothub .")? {
+        let dotfolder = dotfolder?.path();
+        let df = dotfolder
+            .file_name()
+            .unwrap()
+            .to_string_lossy()
+            .into_owned();
+
+        if !dotfolder.is_dir() || df.starts_with('.') || df == "profiles" {
+            continue;
+        }
+
+        for dot in fs::read_dir(&dotfolder)? {
+            let dot = dot?.path();
+            let d = dot.file_name().unwrap().to_string_lossy().into_owned();
+
+            if dot.is_dir() && !d.starts_with('.') {
+                dots.push((df.clone(), d));
+            }
+        }
+    }
+
+    dots.sort();
+
+    Ok(dots)
+}
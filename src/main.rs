@@ -1,46 +1,104 @@
+mod completions;
+mod condition;
+mod daemon;
+mod diff;
+mod doctor;
+mod dot;
+mod edit;
+mod get;
+mod import;
+mod layer;
+mod new;
+mod notification;
+mod output;
+mod pick;
+mod schedule;
+mod schema;
+mod settings;
+mod tui;
+
 use fork::{daemon, Fork};
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     env, fs,
-    io::{self, Write},
     os::unix::fs::symlink,
-    path::{Path, PathBuf},
-    process,
+    path::{Component, Path, PathBuf},
     time::Duration,
 };
 
 use anyhow::{bail, Context, Result};
-use clap::{Arg, ArgMatches, Command};
+use clap::{Arg, ArgAction, Command};
+use clap_complete::{ArgValueCandidates, CompleteEnv};
 use notify::{Config, PollWatcher, Watcher};
-use serde_derive::Deserialize;
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+
+use condition::When;
+use notification::Notifications;
+use output::Format;
 
 #[derive(Debug)]
 struct Profile {
     name: String,
     start: Option<Vec<String>>,
     dots: Option<HashMap<String, String>>,
+    overrides: Vec<ProfileOverride>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
 struct ProfileParsable {
     start: Option<Vec<String>>,
     dots: Option<HashMap<String, String>>,
+    #[serde(default)]
+    overrides: Vec<ProfileOverride>,
+}
+
+// [[overrides]] of a profile, 'start' is replaced, 'dots' are changed entry by entry
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+struct ProfileOverride {
+    when: When,
+    start: Option<Vec<String>>,
+    dots: Option<HashMap<String, String>>,
 }
 
 #[derive(Debug)]
 struct DotFolder {
     name: String,
     dots: Vec<Dot>,
-    config: Option<DotConfig>,
+    config: Option<PartialConfig>,
+    // config.toml of the hub, the lowest layer
+    defaults: PartialConfig,
 }
 
 #[derive(Debug)]
 struct Dot {
     name: String,
-    config: Option<DotConfig>,
+    config: Option<PartialConfig>,
+}
+
+// a .dothub as it's written, a Dot's fields replace the DotFolder's one by one, and the
+// DotFolder's the ones of config.toml
+#[derive(Debug, Deserialize, JsonSchema, Clone, PartialEq, Default)]
+#[serde(deny_unknown_fields)]
+struct PartialConfig {
+    start: Option<String>,
+    kill: Option<String>,
+    reload: Option<String>,
+    destination: Option<String>,
+    reload_on_set: Option<bool>,
+    find_and_replace: Option<bool>,
+    write_back: Option<WriteBack>,
+    notifications: Option<Notifications>,
+    // 'Dot' or 'DotFolder/Dot' whose files are used where this Dot doesn't have them
+    extends: Option<String>,
+    links: Option<Vec<Link>>,
+    overrides: Option<Vec<Override>>,
 }
 
-#[derive(Debug, Deserialize, Clone)]
+// the config of a Dot with every layer put together
+#[derive(Debug, Serialize, Clone, PartialEq)]
 struct DotConfig {
     start: Option<String>,
     kill: Option<String>,
@@ -48,64 +106,215 @@ struct DotConfig {
     destination: String,
     reload_on_set: Option<bool>,
     find_and_replace: Option<bool>,
+    write_back: Option<WriteBack>,
+    notifications: Option<Notifications>,
+    extends: Option<String>,
+    links: Option<Vec<Link>>,
 }
 
-fn main() -> Result<()> {
-    // check if $HOME/.dothub exists, if not, create one
-    let user_home = env::var("HOME").context("No $HOME set!")?;
-    let folder_path = user_home + "/.dothub";
-    let folder_path = Path::new(&folder_path);
+// [[overrides]], the fields replace the ones above when everything in 'when' holds
+#[derive(Debug, Deserialize, JsonSchema, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+struct Override {
+    when: When,
+    start: Option<String>,
+    kill: Option<String>,
+    reload: Option<String>,
+    destination: Option<String>,
+    reload_on_set: Option<bool>,
+    find_and_replace: Option<bool>,
+    write_back: Option<WriteBack>,
+    extends: Option<String>,
+    links: Option<Vec<Link>>,
+}
 
-    if !folder_path.exists() {
-        fs::create_dir(folder_path).context("Couldn't create '.dothub' in your $HOME")?;
+// [[links]], a path inside of the Dot linked somewhere else than 'destination'
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone, PartialEq)]
+#[serde(deny_unknown_fields)]
+struct Link {
+    source: String,
+    destination: String,
+}
+
+// what 'watch' does when a program replaces the symlinks at the destination with real files
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+enum WriteBack {
+    Off,
+    Warn,
+    Adopt,
+}
+
+impl Link {
+    // 'source' is inside of the Dot
+    fn expand_home(self) -> Link {
+        Link {
+            destination: get::expand_home(&self.destination),
+            ..self
+        }
     }
+}
 
-    let profiles_path = &folder_path.join("profiles");
+impl DotFolder {
+    // its .dothub over config.toml, each with the [[overrides]] whose conditions hold
+    fn resolved_config(&self) -> Option<PartialConfig> {
+        let config = self.config.clone()?.with_overrides();
 
-    if !profiles_path.exists() {
-        fs::create_dir(profiles_path).context("Couldn't create 'profiles' in your .dothub .")?;
+        Some(config.or(self.defaults.clone().with_overrides()))
     }
+}
 
-    // go through .dothub/profiles and initialize all profiles
-    let mut profiles: Vec<Profile> = vec![];
+impl Profile {
+    // 'start' and 'dots' with the [[overrides]] whose conditions hold
+    fn resolve(&self) -> (Option<Vec<String>>, Option<HashMap<String, String>>) {
+        let (mut start, mut dots) = (self.start.clone(), self.dots.clone());
 
-    for profile_file in fs::read_dir(profiles_path).unwrap() {
-        let profile_file = profile_file.expect("Couldn't read profile").path();
+        for o in self.overrides.iter().filter(|o| o.when.holds()) {
+            if o.start.is_some() {
+                start = o.start.clone();
+            }
 
-        if profile_file.is_file() {
-            profiles.push(process_profile(profile_file)?);
+            if let Some(override_dots) = &o.dots {
+                dots.get_or_insert_with(HashMap::new)
+                    .extend(override_dots.clone());
+            }
         }
+
+        (start, dots)
     }
+}
 
-    // go through .dothub and initialize all DotFolders with their Dots
-    let mut dot_folders: Vec<DotFolder> = vec![];
+impl PartialConfig {
+    // the [[overrides]] whose conditions hold, folded in
+    fn with_overrides(self) -> PartialConfig {
+        let overrides = self.overrides.clone().unwrap_or_default();
+
+        let config = overrides
+            .into_iter()
+            .filter(|o| o.when.holds())
+            .fold(self, |config, o| PartialConfig {
+                start: o.start.or(config.start),
+                kill: o.kill.or(config.kill),
+                reload: o.reload.or(config.reload),
+                destination: o.destination.or(config.destination),
+                reload_on_set: o.reload_on_set.or(config.reload_on_set),
+                find_and_replace: o.find_and_replace.or(config.find_and_replace),
+                write_back: o.write_back.or(config.write_back),
+                extends: o.extends.or(config.extends),
+                links: o.links.or(config.links),
+                ..config
+            });
+
+        PartialConfig {
+            overrides: None,
+            ..config
+        }
+    }
 
-    for dot_folder in fs::read_dir(folder_path).unwrap() {
-        let dot_folder = dot_folder.expect("Couldn't read DotFolder.").path();
+    // the paths, the commands and everything else keep their '~'
+    fn expand_home(self) -> PartialConfig {
+        let links = |links: Option<Vec<Link>>| {
+            links.map(|links| links.into_iter().map(Link::expand_home).collect())
+        };
 
-        if dot_folder.is_dir() && !dot_folder.ends_with("profiles") {
-            dot_folders.push(process_dotfolder(&dot_folder)?);
+        PartialConfig {
+            destination: self.destination.as_deref().map(get::expand_home),
+            links: links(self.links),
+            overrides: self.overrides.map(|overrides| {
+                overrides
+                    .into_iter()
+                    .map(|o| Override {
+                        destination: o.destination.as_deref().map(get::expand_home),
+                        links: links(o.links),
+                        ..o
+                    })
+                    .collect()
+            }),
+            ..self
+        }
+    }
+
+    // the fields that are set win over the ones of 'lower'
+    fn or(self, lower: PartialConfig) -> PartialConfig {
+        PartialConfig {
+            start: self.start.or(lower.start),
+            kill: self.kill.or(lower.kill),
+            reload: self.reload.or(lower.reload),
+            destination: self.destination.or(lower.destination),
+            reload_on_set: self.reload_on_set.or(lower.reload_on_set),
+            find_and_replace: self.find_and_replace.or(lower.find_and_replace),
+            write_back: self.write_back.or(lower.write_back),
+            notifications: match (self.notifications, lower.notifications) {
+                (Some(higher), Some(lower)) => Some(higher.merge(&lower)),
+                (higher, lower) => higher.or(lower),
+            },
+            extends: self.extends.or(lower.extends),
+            links: self.links.or(lower.links),
+            overrides: self.overrides.or(lower.overrides),
         }
     }
 
-    // helper functions
-    let get_dot_info_from_arg = |arg: &String| -> Result<(&DotFolder, Option<&Dot>)> {
-        let (mut dotfolder_arg, mut dot_arg) = (None, None);
+    fn resolve(self, location: &str) -> Result<DotConfig> {
+        let destination = match self.destination {
+            Some(destination) => destination,
+            None => bail!(
+                "'{}' has no 'destination', set it in the .dothub of the DotFolder or the Dot.",
+                location
+            ),
+        };
+
+        Ok(DotConfig {
+            start: self.start,
+            kill: self.kill,
+            reload: self.reload,
+            destination,
+            reload_on_set: self.reload_on_set,
+            find_and_replace: self.find_and_replace,
+            write_back: self.write_back,
+            notifications: self.notifications,
+            extends: self.extends,
+            links: self.links,
+        })
+    }
+}
+
+// everything inside of .dothub, parsed
+#[derive(Debug)]
+struct Hub {
+    path: PathBuf,
+    notifications: Notifications,
+    profiles: Vec<Profile>,
+    dot_folders: Vec<DotFolder>,
+}
+
+impl Hub {
+    fn load(path: &Path) -> Result<Hub> {
+        let hub_config = process_hub_config(path)?;
+        let (profiles, dot_folders) = process_hub(path, &path.join("profiles"))?;
 
-        let location = arg;
+        Ok(Hub {
+            path: path.to_path_buf(),
+            notifications: hub_config.notifications.unwrap_or_default(),
+            profiles,
+            dot_folders,
+        })
+    }
 
-        if let Some((df_arg, d_arg)) = location.split_once('/') {
-            dotfolder_arg = Some(df_arg);
+    // 'DotFolder/Dot' or 'DotFolder'
+    fn find(&self, location: &str) -> Result<(&DotFolder, Option<&Dot>)> {
+        let mut dot_arg = None;
+
+        let dotfolder_arg = if let Some((df_arg, d_arg)) = location.split_once('/') {
             if !d_arg.is_empty() {
                 dot_arg = Some(d_arg);
             }
+            df_arg
         } else {
-            dotfolder_arg = Some(location);
-        }
-
-        let dotfolder_arg = dotfolder_arg.unwrap();
+            location
+        };
 
-        let dotfolder = dot_folders
+        let dotfolder = self
+            .dot_folders
             .iter()
             .find(|df| df.name == dotfolder_arg)
             .with_context(|| format!("No Dotfolder named '{}'", &dotfolder_arg))?;
@@ -121,195 +330,307 @@ fn main() -> Result<()> {
         }
 
         Ok((dotfolder, None))
-    };
+    }
 
-    let get_active_config = |dot_info: (&DotFolder, Option<&Dot>)| -> Result<DotConfig> {
-        let (dotfolder, dot) = dot_info;
+    fn dot_path(&self, dotfolder: &DotFolder, dot: &Dot) -> PathBuf {
+        self.path.join(&dotfolder.name).join(&dot.name)
+    }
 
-        let df_config = match dotfolder.config.clone() {
-            Some(x) => x,
-            None => {
-                bail!(
-                    "DotFolder '{}' is required to have the field 'destination' filled in it's .dothub!",
-                    &dotfolder.name
-                );
-            }
-        };
+    fn set(&self, location: &str) -> Result<()> {
+        let (dotfolder, dot, config) = self.notifications.validate(
+            &format!("Couldn't set '{}'", location),
+            self.find(location).and_then(|(dotfolder, dot)| {
+                let dot = dot.context("You have to specify the Dot, example 'waybar/neon'.")?;
 
-        if let Some(dot) = dot {
-            if let Some(config) = dot.config.clone() {
-                // merge
-                return Ok(DotConfig {
-                    start: config.start.or(df_config.start),
-                    kill: config.kill.or(df_config.kill),
-                    reload: config.reload.or(df_config.reload),
-                    destination: {
-                        if config.destination.is_empty() {
-                            df_config.destination
-                        } else {
-                            config.destination
-                        }
-                    },
-                    reload_on_set: config.reload_on_set.or(df_config.reload_on_set),
-                    find_and_replace: config.find_and_replace.or(df_config.find_and_replace),
-                });
-            }
+                Ok((dotfolder, dot, get_active_config((dotfolder, Some(dot)))?))
+            }),
+        )?;
+
+        let conf_path = Path::new(&config.destination);
+        let dot_path = self.dot_path(dotfolder, dot);
+
+        dot_notifications(&config, &self.notifications).report(
+            &format!("Set '{}'", location),
+            "The Dot was applied.",
+            dot_set(&config, &dot_path, conf_path),
+        )
+    }
+
+    fn profile_set(&self, name: &str) -> Result<()> {
+        let profile = self.notifications.validate(
+            &format!("Couldn't set profile '{}'", name),
+            self.profiles
+                .iter()
+                .find(|dp| dp.name == name)
+                .context("Profile doesn't exist!"),
+        )?;
+
+        self.notifications.report(
+            &format!("Profile '{}'", name),
+            "The profile was applied.",
+            profile_set(&self.path, profile),
+        )
+    }
+
+    // linked to its destination right now
+    fn is_active(&self, df: &DotFolder, d: &Dot) -> bool {
+        get_active_config((df, Some(d)))
+            .map(|config| dot_is_active(&config, &self.dot_path(df, d)))
+            .unwrap_or(false)
+    }
+
+    // every DotFolder with the Dot that is currently linked to its destination
+    fn status(&self) -> output::Status {
+        output::Status {
+            dotfolders: self
+                .dot_folders
+                .iter()
+                .map(|df| output::StatusDotFolder {
+                    name: df.name.clone(),
+                    active: df
+                        .dots
+                        .iter()
+                        .find(|d| self.is_active(df, d))
+                        .map(|d| d.name.clone()),
+                })
+                .collect(),
+            supervised: vec![],
         }
+    }
 
-        Ok(df_config)
-    };
+    // the plain output only has the names, 'resolve' adds the configs
+    fn list(&self, resolve: bool) -> output::List {
+        output::List {
+            dotfolders: self
+                .dot_folders
+                .iter()
+                .map(|df| output::ListDotFolder {
+                    name: df.name.clone(),
+                    dots: df
+                        .dots
+                        .iter()
+                        .map(|d| {
+                            if !resolve {
+                                return output::ListDot {
+                                    name: d.name.clone(),
+                                    active: false,
+                                    config: None,
+                                    error: None,
+                                };
+                            }
 
-    // commands
-    let args = arguments();
+                            let config = get_active_config((df, Some(d)));
 
-    match args.subcommand() {
-        Some(("set", set_matches)) => {
-            let (dotfolder, dot) =
-                get_dot_info_from_arg(set_matches.get_one::<String>("location").unwrap())?;
-            let config = get_active_config((dotfolder, dot))?;
-            let dot = dot.unwrap();
+                            output::ListDot {
+                                name: d.name.clone(),
+                                active: config
+                                    .as_ref()
+                                    .is_ok_and(|c| dot_is_active(c, &self.dot_path(df, d))),
+                                error: config.as_ref().err().map(|e| format!("{:#}", e)),
+                                config: config.ok(),
+                            }
+                        })
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
 
-            let conf_path = Path::new(&config.destination);
-            let dot_path = format!(
-                "{}/{}/{}",
-                folder_path.to_str().unwrap(),
-                dotfolder.name,
-                dot.name
-            );
-            let dot_path = Path::new(&dot_path);
+    fn profile_list(&self) -> output::Profiles {
+        output::Profiles {
+            profiles: self
+                .profiles
+                .iter()
+                .map(|profile| {
+                    let (start, dots) = profile.resolve();
 
-            dot_set(&config, dot_path, conf_path)?;
+                    output::ListProfile {
+                        name: profile.name.clone(),
+                        start: start.unwrap_or_default(),
+                        dots: dots.unwrap_or_default().into_iter().collect(),
+                    }
+                })
+                .collect(),
         }
-        Some(("watch", set_matches)) => {
-            let (dotfolder, dot) =
-                get_dot_info_from_arg(set_matches.get_one::<String>("location").unwrap())?;
-            let config = get_active_config((dotfolder, dot))?;
-            let dot = dot.unwrap();
+    }
+}
 
-            if dotfolder.config.is_some() {
-                let conf_path = Path::new(&config.destination);
-                let dot_path = format!(
-                    "{}/{}/{}",
-                    folder_path.to_str().unwrap(),
-                    dotfolder.name,
-                    dot.name
-                );
+fn main() -> Result<()> {
+    // answers the shell's completion requests, before anything is printed
+    CompleteEnv::with_factory(command)
+        .var(completions::VAR)
+        .complete();
 
-                println!(
-                    "You are now watching for changes in '{}'.
-Once a change is detected (for example, edits), your Dot will be automaticly reloaded.",
-                    &dot_path
-                );
+    settings::load()?;
 
-                let dot_path = Path::new(&dot_path);
+    let args = command().get_matches();
 
-                dot_set(&config, dot_path, conf_path)?;
+    // check if the hub ($HOME/.dothub unless another one is given) exists, if not, create one
+    let folder_path = &settings::hub_path(args.get_one::<String>("hub"))?;
 
-                // watch for directory changes (writes, moves, etc..)
-                let (tx, rx) = std::sync::mpsc::channel();
+    if !folder_path.exists() {
+        fs::create_dir_all(folder_path)
+            .with_context(|| format!("Couldn't create your hub at '{}'.", folder_path.display()))?;
+    }
 
-                let mut watcher = PollWatcher::new(
-                    tx,
-                    Config::default().with_poll_interval(Duration::from_secs(1)),
-                )
-                .expect("Couldn't create watcher");
+    // commands that run dothub again use the same hub
+    env::set_var("DOTHUB_HOME", folder_path);
 
-                watcher
-                    .watch(dot_path, notify::RecursiveMode::Recursive)
-                    .expect("Couldn't add Dot path to watcher.");
+    let profiles_path = &folder_path.join("profiles");
 
-                for res in rx {
-                    match res {
-                        Ok(ev) => {
-                            if ev.paths[0].is_file() {
-                                dot_reload(&config.clone())?;
-                            }
-                        }
-                        Err(e) => println!("watch error: {:?}", e),
-                    }
-                }
+    if !profiles_path.exists() {
+        fs::create_dir(profiles_path).context("Couldn't create 'profiles' in your .dothub .")?;
+    }
+
+    let use_daemon = !args.get_flag("no-daemon") && env::var_os("DOTHUB_NO_DAEMON").is_none();
+
+    // let a running daemon handle it, it already has the hub parsed
+    if use_daemon {
+        if let Some(request) = daemon::request_from_args(&args) {
+            if let Some(reply) = daemon::send(folder_path, &request)? {
+                print!("{}", reply);
+                return Ok(());
+            }
+        }
+    }
+
+    // they have to work on a hub that can't be loaded
+    if let Some(("doctor", _)) = args.subcommand() {
+        return doctor::doctor(folder_path);
+    }
+
+    if let Some(("edit", matches)) = args.subcommand() {
+        return edit::edit(matches, folder_path);
+    }
+
+    if let Some(("completions", matches)) = args.subcommand() {
+        return completions::completions(matches.get_one::<String>("shell").unwrap());
+    }
+
+    if let Some(("schema", matches)) = args.subcommand() {
+        println!(
+            "{}",
+            schema::schema(matches.get_one::<String>("kind").unwrap())
+        );
+        return Ok(());
+    }
+
+    // a broken .dothub is worth a notification when we are run from a keybinding
+    let hub = match args.subcommand_name() {
+        Some("set" | "watch" | "profile" | "pick") => {
+            let notifications = process_hub_config(folder_path)?
+                .notifications
+                .unwrap_or_default();
+
+            notifications.validate("Couldn't read your .dothub", Hub::load(folder_path))?
+        }
+        _ => Hub::load(folder_path)?,
+    };
+
+    // commands
+    match args.subcommand() {
+        Some(("set", set_matches)) => {
+            hub.set(set_matches.get_one::<String>("location").unwrap())?;
+        }
+        Some(("watch", set_matches)) => {
+            let (dotfolder, dot) = hub.find(set_matches.get_one::<String>("location").unwrap())?;
+            let dot =
+                dot.context("You have to specify the Dot to watch, example 'waybar/neon'.")?;
+
+            if dotfolder.config.is_some() {
+                let write_back = set_matches
+                    .get_one::<String>("write-back")
+                    .map(|mode| match mode.as_str() {
+                        "warn" => WriteBack::Warn,
+                        "adopt" => WriteBack::Adopt,
+                        _ => WriteBack::Off,
+                    });
+
+                dot_watch(folder_path, &hub.notifications, dotfolder, dot, write_back)?;
             } else {
                 bail!("DotFolder has to have a .dothub with at least 'destination' filled!")
             }
         }
         Some(("list", _)) => {
-            for df in dot_folders {
-                println!("{}/", df.name);
-                for d in df.dots {
-                    println!("  {}", d.name);
-                }
-            }
+            let format = Format::from_args(&args);
+
+            print!(
+                "{}",
+                output::render(&hub.list(format != Format::Plain), format)?
+            );
         }
         Some(("start", matches)) => {
-            let config = get_active_config(get_dot_info_from_arg(
-                matches.get_one::<String>("location").unwrap(),
-            )?)?;
+            let config =
+                get_active_config(hub.find(matches.get_one::<String>("location").unwrap())?)?;
 
             dot_start(&config)?;
         }
         Some(("kill", matches)) => {
-            let config = get_active_config(get_dot_info_from_arg(
-                matches.get_one::<String>("location").unwrap(),
-            )?)?;
+            let config =
+                get_active_config(hub.find(matches.get_one::<String>("location").unwrap())?)?;
 
             dot_kill(&config)?;
         }
         Some(("reload", matches)) => {
-            let config = get_active_config(get_dot_info_from_arg(
-                matches.get_one::<String>("location").unwrap(),
-            )?)?;
+            let config =
+                get_active_config(hub.find(matches.get_one::<String>("location").unwrap())?)?;
 
             dot_reload(&config)?;
         }
         Some(("run", matches)) => {
-            let prog = matches.get_one("Program").unwrap();
+            let prog = matches.get_one::<String>("Program").unwrap();
 
             run(prog);
         }
+        Some(("diff", matches)) => {
+            diff::diff(matches, &hub)?;
+        }
+        Some(("pick", matches)) => {
+            pick::pick(matches, &hub, use_daemon)?;
+        }
+        Some(("tui", _)) => {
+            tui::tui(hub, use_daemon)?;
+        }
+        Some(("status", _)) => {
+            print!(
+                "{}",
+                output::render(&hub.status(), Format::from_args(&args))?
+            );
+        }
+        Some(("daemon", _)) => {
+            daemon::serve(hub)?;
+        }
+        Some(("doctor", _)) => {
+            doctor::doctor(folder_path)?;
+        }
+        Some(("dot", matches)) => {
+            dot::dot(matches, &hub)?;
+        }
+        Some(("new", matches)) => {
+            new::new(matches, folder_path)?;
+        }
+        Some(("import", matches)) => {
+            import::import(matches, folder_path)?;
+        }
+        Some(("schedule", matches)) => match matches.subcommand() {
+            Some(("run", _)) => {
+                schedule::Scheduler::new(&hub.path).run()?;
+            }
+            _ => unreachable!(),
+        },
         Some(("get", matches)) => {
             // ew, only temporary I hope
-            dot_get(matches, folder_path)?;
+            get::dot_get(matches, folder_path)?;
         }
         Some(("profile", matches)) => match matches.subcommand() {
             Some(("set", pmatches)) => {
-                let to_set = pmatches.get_one::<String>("Profile").unwrap();
-
-                let profile = profiles
-                    .iter()
-                    .find(|dp| &dp.name == to_set)
-                    .context("Profile doesn't exist!")?;
-
-                // run profile on_start commands
-                if let Some(start) = &profile.start {
-                    for cmd in start {
-                        process::Command::new("sh")
-                            .args(["-c", cmd])
-                            .output()
-                            .context("Couldn't run command '{cmd}'")?;
-                    }
-                }
-
-                // set all dots from profile
-                if let Some(pdots) = &profile.dots {
-                    for (df, dt) in pdots.iter() {
-                        let dotfolder_path = folder_path.join(df);
-                        let dot_path = dotfolder_path.join(dt);
-
-                        let dotfolder = process_dotfolder(&dotfolder_path)?;
-                        let dot = process_dot(&dot_path)?;
-                        let config = get_active_config((&dotfolder, Some(&dot)))?;
-                        let conf_path = Path::new(&config.destination);
-
-                        dot_set(&config, &dot_path, conf_path)?;
-                    }
-                } else {
-                    println!("There are no Dots specified in 'dots'!");
-                }
+                hub.profile_set(pmatches.get_one::<String>("Profile").unwrap())?;
             }
             Some(("list", _)) => {
-                for dp in profiles {
-                    println!("{}", dp.name);
-                }
+                print!(
+                    "{}",
+                    output::render(&hub.profile_list(), Format::from_args(&args))?
+                );
             }
             _ => unreachable!(),
         },
@@ -319,7 +640,84 @@ Once a change is detected (for example, edits), your Dot will be automaticly rel
     Ok(())
 }
 
+fn profile_set(folder_path: &Path, profile: &Profile) -> Result<()> {
+    let (start, dots) = profile.resolve();
+
+    // run profile on_start commands
+    if let Some(start) = &start {
+        for cmd in start {
+            settings::shell_command(cmd)
+                .output()
+                .with_context(|| format!("Couldn't run command '{cmd}'"))?;
+        }
+    }
+
+    // set all dots from profile
+    if let Some(pdots) = &dots {
+        for (df, dt) in pdots.iter() {
+            let dotfolder_path = folder_path.join(df);
+            let dot_path = dotfolder_path.join(dt);
+
+            let dotfolder = process_dotfolder(&dotfolder_path)?;
+            let dot = process_dot(&dot_path)?;
+            let config = get_active_config((&dotfolder, Some(&dot)))?;
+            let conf_path = Path::new(&config.destination);
+
+            dot_set(&config, &dot_path, conf_path)?;
+        }
+    } else {
+        println!("There are no Dots specified in 'dots'!");
+    }
+
+    Ok(())
+}
+
+// the notification settings of a Dot, on top of the global ones
+fn dot_notifications(config: &DotConfig, global: &Notifications) -> Notifications {
+    match &config.notifications {
+        Some(notifications) => notifications.merge(global),
+        None => global.clone(),
+    }
+}
+
+fn get_active_config(dot_info: (&DotFolder, Option<&Dot>)) -> Result<DotConfig> {
+    let (dotfolder, dot) = dot_info;
+
+    let df_config = match dotfolder.resolved_config() {
+        // only a Dot can extend another one
+        Some(x) => PartialConfig { extends: None, ..x },
+        None => {
+            bail!(
+                "DotFolder '{}' is required to have a .dothub!",
+                &dotfolder.name
+            );
+        }
+    };
+
+    match dot {
+        Some(dot) => {
+            let config = match dot.config.clone() {
+                Some(config) => config.with_overrides().or(df_config),
+                None => df_config,
+            };
+
+            config.resolve(&format!("{}/{}", dotfolder.name, dot.name))
+        }
+        None => df_config.resolve(&dotfolder.name),
+    }
+}
+
 fn dot_set(config: &DotConfig, dot_path: &Path, conf_path: &Path) -> Result<()> {
+    let dot_path = &layer::materialize(config, dot_path)?;
+    let mapped = dot_mapped_links(config, dot_path)?;
+
+    // check them before anything is linked
+    for (_, source) in &mapped {
+        if fs::symlink_metadata(source).is_err() {
+            bail!("'{}' from [[links]] isn't in the Dot.", source.display());
+        }
+    }
+
     if !conf_path.exists() {
         if let Some(parent_path) = conf_path.parent() {
             if !parent_path.exists() {
@@ -328,30 +726,37 @@ fn dot_set(config: &DotConfig, dot_path: &Path, conf_path: &Path) -> Result<()>
         }
     }
 
+    // an empty 'destination' only makes sense with [[links]]
     match config.find_and_replace {
-        Some(value) if value => fs::read_dir(dot_path).unwrap().for_each(|item| {
-            let item = item.expect("Couldn't read destination.").path();
-            let destination = conf_path.join(item.file_name().unwrap());
-
-            if item.is_file() {
-                fs::remove_file(&destination).expect("Couldn't remove old dot file.");
-            } else if item.is_dir() {
-                fs::remove_dir_all(&destination).expect("Couldn't remove the old Dot folder.");
+        _ if config.destination.is_empty() => {}
+        Some(value) if value => {
+            if !conf_path.exists() {
+                fs::create_dir_all(conf_path).context("Couldn't create the destination folder.")?;
             }
 
-            symlink(item, destination).expect("Couldn't create a symlink");
-        }),
-        _ => {
-            if conf_path.is_file() {
-                fs::remove_file(conf_path).expect("Coudln't remove old dot file.");
-            } else if conf_path.is_dir() {
-                fs::remove_dir_all(conf_path).expect("Couldn't remove the old Dot folder.");
+            for entry in dot_entries(dot_path)? {
+                let destination = conf_path.join(&entry);
+
+                remove_existing(&destination)?;
+                symlink(dot_path.join(&entry), destination).expect("Couldn't create a symlink");
             }
+        }
+        _ => {
+            remove_existing(conf_path)?;
 
             symlink(dot_path, conf_path).expect("Couldn't create a symlink.");
         }
     }
 
+    for (destination, source) in mapped {
+        if let Some(parent_path) = destination.parent() {
+            fs::create_dir_all(parent_path).context("Couldn't create parent path recursively.")?;
+        }
+
+        remove_existing(&destination)?;
+        symlink(&source, &destination).context("Couldn't create a symlink.")?;
+    }
+
     // if 'reload' exists or both 'start' and 'kill' are specified, we can reload
     // only if 'reload_on_set' is set to 'true', which is the default value.
     if config.reload.is_some() || (config.start.is_some() && config.kill.is_some()) {
@@ -365,10 +770,452 @@ fn dot_set(config: &DotConfig, dot_path: &Path, conf_path: &Path) -> Result<()>
     Ok(())
 }
 
+// removes a file, folder or symlink, without following symlinks
+fn remove_existing(path: &Path) -> Result<()> {
+    match fs::symlink_metadata(path) {
+        Ok(meta) if meta.is_dir() => {
+            fs::remove_dir_all(path).context("Couldn't remove the old Dot folder.")?
+        }
+        Ok(_) => fs::remove_file(path).context("Couldn't remove old dot file.")?,
+        Err(_) => {}
+    }
+
+    Ok(())
+}
+
+// removes the symlinks at the destination that point into the Dot
+fn dot_unlink(config: &DotConfig, dot_path: &Path) -> Result<()> {
+    let conf_path = Path::new(&config.destination);
+    let dot_path = &layer::link_source(config, dot_path);
+
+    let links = match config.find_and_replace {
+        Some(value) if value => match conf_path.read_dir() {
+            Ok(entries) => entries.filter_map(|e| e.ok()).map(|e| e.path()).collect(),
+            Err(_) => vec![],
+        },
+        _ => vec![conf_path.to_path_buf()],
+    };
+
+    let mapped = dot_mapped_links(config, dot_path)?;
+
+    for link in links
+        .into_iter()
+        .chain(mapped.into_iter().map(|(link, _)| link))
+    {
+        if let Ok(target) = fs::read_link(&link) {
+            if target.starts_with(dot_path) {
+                fs::remove_file(&link).context("Couldn't remove an old symlink.")?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// (link, source) of every [[links]] entry
+fn dot_mapped_links(config: &DotConfig, dot_path: &Path) -> Result<Vec<(PathBuf, PathBuf)>> {
+    let mut links = vec![];
+
+    for link in config.links.iter().flatten() {
+        let source = Path::new(&link.source);
+        let destination = PathBuf::from(&link.destination);
+
+        if source
+            .components()
+            .any(|c| !matches!(c, Component::Normal(_)))
+        {
+            bail!(
+                "'{}' in [[links]] has to be a path inside of the Dot.",
+                link.source
+            );
+        }
+
+        if !destination.is_absolute() {
+            bail!(
+                "The destination of '{}' in [[links]] has to be an absolute path.",
+                link.source
+            );
+        }
+
+        links.push((destination, dot_path.join(source)));
+    }
+
+    Ok(links)
+}
+
+// the folders holding the links of a Dot
+fn write_back_watch_paths(config: &DotConfig) -> Vec<PathBuf> {
+    let conf_path = Path::new(&config.destination);
+
+    let mut paths = match (config.find_and_replace, conf_path.parent()) {
+        _ if config.destination.is_empty() => vec![],
+        (Some(true), _) | (_, None) => vec![conf_path.to_path_buf()],
+        (_, Some(parent)) => vec![parent.to_path_buf()],
+    };
+
+    for link in config.links.iter().flatten() {
+        if let Some(parent) = Path::new(&link.destination).parent() {
+            paths.push(parent.to_path_buf());
+        }
+    }
+
+    paths.sort();
+    paths.dedup();
+
+    paths
+}
+
+// finds links at the destination that a program replaced with real files (for example by saving
+// through a temporary file), with 'adopt' they are copied back into the Dot and linked again
+fn dot_write_back(config: &DotConfig, dot_path: &Path, mode: WriteBack) -> Result<Vec<PathBuf>> {
+    let conf_path = Path::new(&config.destination);
+    let link_source = layer::link_source(config, dot_path);
+
+    // changes are copied into the Dot itself, also when it extends another one
+    let mut links = match config.find_and_replace {
+        _ if config.destination.is_empty() => vec![],
+        Some(value) if value => dot_entries(&link_source)?
+            .into_iter()
+            .map(|entry| (conf_path.join(&entry), dot_path.join(&entry)))
+            .collect(),
+        _ => vec![(conf_path.to_path_buf(), dot_path.to_path_buf())],
+    };
+    links.extend(dot_mapped_links(config, dot_path)?);
+
+    let mut replaced = vec![];
+
+    for (link, source) in links {
+        match fs::symlink_metadata(&link) {
+            Ok(meta) if !meta.file_type().is_symlink() => {}
+            _ => continue,
+        }
+
+        if mode == WriteBack::Adopt {
+            let mut skipped = vec![];
+
+            if link.is_dir() {
+                get::copy_tree(&link, &source, &mut skipped)
+                    .context("Couldn't copy changes back into your .dothub .")?;
+            } else if source == dot_path {
+                bail!(
+                    "'{}' was replaced by a file, but the Dot is a folder.",
+                    link.display()
+                );
+            } else {
+                remove_existing(&source)?;
+                get::copy_tree(&link, &source, &mut skipped)
+                    .context("Couldn't copy changes back into your .dothub .")?;
+            }
+
+            if !skipped.is_empty() {
+                bail!(
+                    "'{}' contains sockets, fifos or devices, it can't be copied back.",
+                    link.display()
+                );
+            }
+
+            // the merged tree has to pick up the copied file first
+            let target = match source.strip_prefix(dot_path) {
+                Ok(entry) if !entry.as_os_str().is_empty() => {
+                    layer::materialize(config, dot_path)?.join(entry)
+                }
+                _ => layer::materialize(config, dot_path)?,
+            };
+
+            remove_existing(&link)?;
+            symlink(&target, &link).context("Couldn't create a symlink.")?;
+        }
+
+        replaced.push(link);
+    }
+
+    Ok(replaced)
+}
+
+// whether the destination currently links into the Dot
+fn dot_is_active(config: &DotConfig, dot_path: &Path) -> bool {
+    let conf_path = Path::new(&config.destination);
+    let dot_path = &layer::link_source(config, dot_path);
+
+    let mut links = match config.find_and_replace {
+        Some(value) if value => match dot_entries(dot_path) {
+            Ok(entries) => entries.iter().map(|e| conf_path.join(e)).collect(),
+            Err(_) => vec![],
+        },
+        _ => vec![conf_path.to_path_buf()],
+    };
+
+    if let Ok(mapped) = dot_mapped_links(config, dot_path) {
+        links.extend(mapped.into_iter().map(|(link, _)| link));
+    }
+
+    links.iter().any(|link| match fs::read_link(link) {
+        Ok(target) => target.starts_with(dot_path),
+        Err(_) => false,
+    })
+}
+
+// top-level entries of a Dot that get linked, sorted so they can be compared
+fn dot_entries(dot_path: &Path) -> Result<Vec<String>> {
+    let mut entries = vec![];
+
+    for entry in dot_path.read_dir().context("Couldn't read Dot.")? {
+        let name = entry.context("Couldn't read Dot.")?.file_name();
+        let name = name
+            .to_str()
+            .context("Dot contains a non UTF-8 file name.")?;
+
+        if name != ".dothub" {
+            entries.push(name.to_owned());
+        }
+    }
+
+    entries.sort();
+
+    Ok(entries)
+}
+
+// every file of a folder, by its path inside of it, symlinks to folders aren't followed
+fn dot_files(dot_path: &Path) -> Result<Vec<PathBuf>> {
+    fn walk(root: &Path, relative: &Path, files: &mut Vec<PathBuf>) -> Result<()> {
+        let mut entries = fs::read_dir(root.join(relative))
+            .with_context(|| format!("Couldn't read '{}'.", root.join(relative).display()))?
+            .map(|entry| entry.map(|entry| entry.file_name()))
+            .collect::<Result<Vec<_>, _>>()?;
+        entries.sort();
+
+        for name in entries {
+            let path = relative.join(&name);
+
+            match fs::symlink_metadata(root.join(&path))?.is_dir() {
+                true => walk(root, &path, files)?,
+                false => files.push(path),
+            }
+        }
+
+        Ok(())
+    }
+
+    let mut files = vec![];
+    walk(dot_path, Path::new(""), &mut files)?;
+
+    Ok(files)
+}
+
+// parse the DotFolder and the Dot again, from disk
+fn resolve_config(dotfolder_path: &Path, dot_path: &Path) -> Result<DotConfig> {
+    let dotfolder = process_dotfolder(dotfolder_path)?;
+    let dot = process_dot(dot_path)?;
+
+    get_active_config((&dotfolder, Some(&dot)))
+}
+
+fn dot_watch(
+    folder_path: &Path,
+    global_notifications: &Notifications,
+    dotfolder: &DotFolder,
+    dot: &Dot,
+    write_back: Option<WriteBack>,
+) -> Result<()> {
+    let dotfolder_path = folder_path.join(&dotfolder.name);
+    let dot_path = dotfolder_path.join(&dot.name);
+    let dotfolder_config_path = dotfolder_path.join(".dothub");
+    let dot_config_path = dot_path.join(".dothub");
+
+    let mut config = get_active_config((dotfolder, Some(dot)))?;
+    let mut notifications = dot_notifications(&config, global_notifications);
+    let summary = format!("Watching '{}/{}'", dotfolder.name, dot.name);
+
+    println!(
+        "You are now watching for changes in '{}'.
+Once a change is detected (for example, edits), your Dot will be automaticly reloaded.",
+        dot_path.display()
+    );
+
+    dot_set(&config, &dot_path, Path::new(&config.destination))?;
+    let mut entries = dot_entries(&layer::link_source(&config, &dot_path))?;
+    let mut warned: HashSet<PathBuf> = HashSet::new();
+
+    // watch for directory changes (writes, moves, etc..)
+    let (tx, rx) = std::sync::mpsc::channel();
+
+    let mut watcher = PollWatcher::new(
+        tx,
+        Config::default().with_poll_interval(Duration::from_secs(1)),
+    )
+    .expect("Couldn't create watcher");
+
+    // the Dots it extends are watched too, a change in any of them changes the merged tree
+    let mut layers = layer::layers(&dot_path)?;
+
+    for layer in &layers {
+        watcher
+            .watch(layer, notify::RecursiveMode::Recursive)
+            .expect("Couldn't add Dot path to watcher.");
+    }
+    // the DotFolder's .dothub lives next to the Dots, not inside of them
+    watcher
+        .watch(&dotfolder_path, notify::RecursiveMode::NonRecursive)
+        .expect("Couldn't add DotFolder path to watcher.");
+
+    // the links themselves live in the destination (or next to it)
+    let mut destination_watch = write_back_watch_paths(&config);
+    for path in &destination_watch {
+        let _ = watcher.watch(path, notify::RecursiveMode::NonRecursive);
+    }
+
+    for res in rx {
+        let ev = match res {
+            Ok(ev) => ev,
+            Err(e) => {
+                println!("watch error: {:?}", e);
+                continue;
+            }
+        };
+
+        let mode = write_back.or(config.write_back).unwrap_or(WriteBack::Off);
+
+        if mode != WriteBack::Off {
+            match dot_write_back(&config, &dot_path, mode) {
+                Ok(replaced) => {
+                    for path in &replaced {
+                        if mode == WriteBack::Adopt {
+                            println!("Copied '{}' back into your .dothub.", path.display());
+                        } else if warned.insert(path.clone()) {
+                            let msg = format!(
+                                "'{}' was replaced by a real file, changes made there aren't in your .dothub.",
+                                path.display()
+                            );
+
+                            println!("{}", msg);
+                            notifications.send(notification::Outcome::Invalid, &summary, &msg);
+                        }
+                    }
+
+                    warned.retain(|path| replaced.contains(path));
+                }
+                Err(e) => {
+                    let e = e.context("Couldn't write back changes.");
+
+                    println!("{:?}", e);
+                    notifications.send(notification::Outcome::Error, &summary, &format!("{:#}", e));
+                }
+            }
+        }
+
+        let config_changed = ev
+            .paths
+            .iter()
+            .any(|p| p == &dotfolder_config_path || p == &dot_config_path);
+        let content_changed = ev.paths.iter().any(|p| {
+            layers
+                .iter()
+                .any(|layer| p.starts_with(layer) && p != layer && p != &dot_config_path)
+        });
+
+        if !config_changed && !content_changed {
+            continue;
+        }
+
+        let mut relink = false;
+
+        if config_changed {
+            match resolve_config(&dotfolder_path, &dot_path) {
+                Ok(new_config) if new_config != config => {
+                    println!(
+                        "The .dothub of '{}' changed, using the new config.",
+                        dot.name
+                    );
+
+                    relink = new_config.destination != config.destination
+                        || new_config.find_and_replace != config.find_and_replace
+                        || new_config.extends != config.extends
+                        || new_config.links != config.links;
+
+                    if relink {
+                        dot_unlink(&config, &dot_path)?;
+
+                        if new_config.extends.is_none() {
+                            layer::remove_merged(&dot_path)?;
+                        }
+                    }
+
+                    config = new_config;
+                    notifications = dot_notifications(&config, global_notifications);
+
+                    for path in &destination_watch {
+                        let _ = watcher.unwatch(path);
+                    }
+                    destination_watch = write_back_watch_paths(&config);
+                    for path in &destination_watch {
+                        let _ = watcher.watch(path, notify::RecursiveMode::NonRecursive);
+                    }
+                }
+                Ok(_) => {}
+                Err(e) => {
+                    println!(
+                        "Couldn't parse the changed .dothub, keeping the old config: {:?}",
+                        e
+                    );
+                    let _ = notifications.validate::<()>(&summary, Err(e));
+                    continue;
+                }
+            }
+        }
+
+        if config.extends.is_some() || layers.len() > 1 {
+            let merged = layer::layers(&dot_path).and_then(|new_layers| {
+                if new_layers != layers {
+                    for layer in &layers {
+                        let _ = watcher.unwatch(layer);
+                    }
+                    for layer in &new_layers {
+                        let _ = watcher.watch(layer, notify::RecursiveMode::Recursive);
+                    }
+                    layers = new_layers;
+                }
+
+                layer::materialize(&config, &dot_path)
+            });
+
+            if let Err(e) = merged {
+                println!("{:?}", e);
+                let _ = notifications.validate::<()>(&summary, Err(e));
+                continue;
+            }
+        }
+
+        let new_entries = dot_entries(&layer::link_source(&config, &dot_path))?;
+
+        let reloaded = if relink || new_entries != entries {
+            entries = new_entries;
+
+            // drop the links of removed entries before linking the new set
+            dot_unlink(&config, &dot_path)
+                .and_then(|_| dot_set(&config, &dot_path, Path::new(&config.destination)))
+        } else if config_changed || ev.paths.iter().any(|p| p.is_file()) {
+            dot_reload(&config)
+        } else {
+            continue;
+        };
+
+        // keep watching, a broken edit is usually fixed by the next one
+        if let Err(e) = notifications.report(&summary, "Reloaded after a change.", reloaded) {
+            println!("{:?}", e);
+        }
+    }
+
+    Ok(())
+}
+
 // run a program, make it a daemon, exit
-fn run(prog: &String) {
+fn run(prog: &str) {
+    settings::debug(&format!("running '{}'", prog));
+
     if let Ok(Fork::Child) = daemon(false, false) {
-        let _ = exec::Command::new("sh").args(&["-c", prog]).exec();
+        let _ = exec::Command::new(settings::shell())
+            .args(&["-c", prog])
+            .exec();
     }
 }
 
@@ -384,8 +1231,7 @@ fn dot_start(config: &DotConfig) -> Result<()> {
 
 fn dot_kill(config: &DotConfig) -> Result<()> {
     if let Some(kill_cmd) = &config.kill {
-        process::Command::new("sh")
-            .args(["-c", kill_cmd])
+        settings::shell_command(kill_cmd)
             .output()
             .context("Couldn't kill Dot.")?;
     } else {
@@ -397,13 +1243,11 @@ fn dot_kill(config: &DotConfig) -> Result<()> {
 
 fn dot_reload(config: &DotConfig) -> Result<()> {
     if let Some(reload_cmd) = &config.reload {
-        process::Command::new("sh")
-            .args(["-c", reload_cmd])
+        settings::shell_command(reload_cmd)
             .output()
             .context("Couldn't reload Dot.")?;
     } else if let (Some(start_cmd), Some(kill_cmd)) = (&config.start, &config.kill) {
-        process::Command::new("sh")
-            .args(["-c", &format!("{} && {}", &kill_cmd, &start_cmd)])
+        settings::shell_command(&format!("{} && {}", &kill_cmd, &start_cmd))
             .output()
             .context("Couldn't reload Dot.")?;
     } else {
@@ -413,105 +1257,57 @@ fn dot_reload(config: &DotConfig) -> Result<()> {
     Ok(())
 }
 
-fn dot_get(matches: &ArgMatches, folder_path: &Path) -> Result<()> {
-    // check if all paths given are valid
-    let paths = matches
-        .get_many::<String>("paths")
-        .unwrap()
-        .filter_map(|p| {
-            let path = Path::new(p);
-            if !path.exists() {
-                println!("'{}' doesn't exist!", p);
-                None
-            } else {
-                Some(path)
-            }
-        });
-
-    println!(
-        "For every path, input it's location in your .dothub. Example: 'polybar/red_one'.
-Nonexsitent folders are gonna be created.
-Existent 'Dots' are gonna be ereased.\n"
-    );
+// .dothub/config.toml, everything a .dothub can have, for every DotFolder
+fn process_hub_config(folder_path: &Path) -> Result<PartialConfig> {
+    let config_path = folder_path.join("config.toml");
 
-    // helper function, i am lazy, I miss Python
-    let input = |msg: &str| -> String {
-        println!("{msg}");
+    if !config_path.is_file() {
+        return Ok(PartialConfig::default());
+    }
 
-        let mut buf = "".to_string();
+    let config_file = fs::read_to_string(&config_path).context("Couldn't read config.toml .")?;
 
-        io::stdout()
-            .flush()
-            .expect("Flush broken, try a different toilet.");
-        io::stdin()
-            .read_line(&mut buf)
-            .expect("Reading from stdin failed.");
+    schema::parse_dothub(&config_path, &config_file)
+}
 
-        buf
-    };
+// config.toml, with 'reload_on_set' of the settings below it
+fn hub_defaults(folder_path: &Path) -> Result<PartialConfig> {
+    let config = process_hub_config(folder_path)?;
 
-    // for each path arg, input it's .dothub location
-    paths.for_each(|p| {
-        'main: loop {
-            let location = input(&format!("'{}': ", p.to_str().unwrap()));
+    Ok(PartialConfig {
+        reload_on_set: config.reload_on_set.or(settings::get().reload_on_set),
+        ..config
+    })
+}
 
-            // trim the leading '\n'
-            let location = location.trim();
+fn process_hub(folder_path: &Path, profiles_path: &Path) -> Result<(Vec<Profile>, Vec<DotFolder>)> {
+    // go through .dothub/profiles and initialize all profiles
+    let mut profiles: Vec<Profile> = vec![];
 
-            // Dotfolder/Dot parsing
-            if let Some((_, d)) = location.split_once('/') {
-                let final_destination = folder_path.join(location);
+    for profile_file in fs::read_dir(profiles_path).unwrap() {
+        let profile_file = profile_file.expect("Couldn't read profile").path();
 
-                if d.is_empty() {
-                    println!("You have to input the 'Dot'.");
-                    continue;
-                }
+        if profile_file.is_file() {
+            profiles.push(process_profile(profile_file)?);
+        }
+    }
 
-                // if the destination in .dothub already exists, ask if it should be overwritten
-                if final_destination.exists() {
-                    loop {
-                        let user_choice = input(&format!(
-                            "'{}' already exists, do you want to replace it? [y/n]: ",
-                            location
-                        ));
-
-                        if let Some(choice) = user_choice.to_lowercase().chars().next() {
-                            match choice {
-                                'y' => {
-                                    fs::remove_dir_all(&final_destination).unwrap();
-                                    break;
-                                }
-                                'n' => {
-                                    break 'main;
-                                }
-                                _ => {}
-                            }
-                        }
-                    }
-                } else {
-                    fs::create_dir_all(&final_destination)
-                        .expect("Couldn't create a new 'Dot' in your .dothub .");
-                }
+    // go through .dothub and initialize all DotFolders with their Dots
+    let mut dot_folders: Vec<DotFolder> = vec![];
 
-                if p.is_file() {
-                    fs::copy(p, final_destination.join(p.file_name().unwrap()))
-                        .expect("Couldn't copy dot file over to your .dothub .");
-                } else {
-                    let mut options = fs_extra::dir::CopyOptions::new();
-                    options.content_only = true;
+    for dot_folder in fs::read_dir(folder_path).unwrap() {
+        let dot_folder = dot_folder.expect("Couldn't read DotFolder.").path();
 
-                    fs_extra::dir::copy(p, final_destination, &options)
-                        .expect("Couldn't copy dot folder over to your .dothub .");
-                }
+        let hidden = dot_folder
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'));
 
-                break;
-            } else {
-                println!("The format is 'DotFolder/Dot'");
-            }
+        if dot_folder.is_dir() && !dot_folder.ends_with("profiles") && !hidden {
+            dot_folders.push(process_dotfolder(&dot_folder)?);
         }
-    });
+    }
 
-    Ok(())
+    Ok((profiles, dot_folders))
 }
 
 fn process_profile(path: PathBuf) -> Result<Profile> {
@@ -525,19 +1321,20 @@ fn process_profile(path: PathBuf) -> Result<Profile> {
 
     let profile_contents = fs::read_to_string(&path).expect("Couldn't read profile.");
 
-    let parsed: ProfileParsable =
-        toml::from_str(&profile_contents).context("Couldn't parse a profile.")?;
+    let parsed: ProfileParsable = schema::parse(&path, &profile_contents)?;
 
     Ok(Profile {
         name,
         start: parsed.start,
         dots: parsed.dots,
+        overrides: parsed.overrides,
     })
 }
 
 fn process_dotfolder(path: &Path) -> Result<DotFolder> {
     let name = path.file_name().unwrap().to_str().unwrap().to_owned();
-    let mut config: Option<DotConfig> = None;
+    let mut config: Option<PartialConfig> = None;
+    let defaults = hub_defaults(path.parent().unwrap())?;
 
     let dots_paths = path.read_dir().unwrap();
 
@@ -546,22 +1343,13 @@ fn process_dotfolder(path: &Path) -> Result<DotFolder> {
             let dot_path = &dot_path.expect("Couldn't read Dot.").path();
             let dot_path_name = dot_path.file_name().unwrap().to_str().unwrap();
 
-            if dot_path.is_dir() {
+            // hidden folders are unfinished or replaced Dots from 'get'
+            if dot_path.is_dir() && !dot_path_name.starts_with('.') {
                 return Some(process_dot(dot_path));
             } else if dot_path.is_file() && dot_path_name == ".dothub" {
-                let user_home = match env::var("HOME").context("No $HOME set!") {
-                    Ok(value) => value,
-                    Err(e) => return Some(Err(e)),
-                };
-
-                let config_file = fs::read_to_string(dot_path)
-                    .expect("Couldn't read .dothub .")
-                    .replace('~', &user_home);
+                let config_file = fs::read_to_string(dot_path).expect("Couldn't read .dothub .");
 
-                let parsed = toml::from_str(&config_file)
-                    .with_context(|| format!("'{}' .dothub couldn't be parsed.", name));
-
-                match parsed {
+                match schema::parse_dothub(dot_path, &config_file) {
                     Ok(conf) => config = Some(conf),
                     Err(e) => return Some(Err(e)),
                 }
@@ -571,14 +1359,19 @@ fn process_dotfolder(path: &Path) -> Result<DotFolder> {
         .collect();
 
     match dots {
-        Ok(dots) => Ok(DotFolder { name, dots, config }),
+        Ok(dots) => Ok(DotFolder {
+            name,
+            dots,
+            config,
+            defaults,
+        }),
         Err(e) => Err(e),
     }
 }
 
 fn process_dot(path: &Path) -> Result<Dot> {
     let name = path.file_name().unwrap().to_str().unwrap().to_owned();
-    let mut config: Option<DotConfig> = None;
+    let mut config: Option<PartialConfig> = None;
 
     let dots_files = path.read_dir().unwrap();
     for dot_path in dots_files {
@@ -586,38 +1379,71 @@ fn process_dot(path: &Path) -> Result<Dot> {
         let dot_path_name = dot_path.file_name().unwrap().to_str().unwrap();
 
         if dot_path.is_file() && dot_path_name == ".dothub" {
-            let user_home = env::var("HOME").context("No $HOME set!")?;
+            let config_file = fs::read_to_string(&dot_path).expect("Couldn't read .dothub .");
 
-            let config_file = fs::read_to_string(dot_path)
-                .expect("Couldn't read .dothub .")
-                .replace('~', &user_home);
-
-            config = Some(toml::from_str(&config_file).context("Dot .dothub couldn't be parsed")?);
+            config = Some(schema::parse_dothub(&dot_path, &config_file)?);
         }
     }
 
     Ok(Dot { name, config })
 }
 
-fn arguments() -> clap::ArgMatches {
+// the arguments shared by all 'import' subcommands
+fn import_command(name: &'static str, path_help: &'static str) -> Command {
+    Command::new(name)
+        .about(format!("Imports from {}.", name))
+        .arg(Arg::new("path").help(path_help).required(true))
+        .arg(
+            Arg::new("dot")
+                .long("dot")
+                .help("Name of the Dot every DotFolder gets.")
+                .default_value("imported"),
+        )
+        .arg(Arg::new("target").long("target").help(
+            "Where the files are deployed. Defaults to $HOME, or the parent of the stow directory.",
+        ))
+}
+
+fn command() -> Command {
     Command::new("dothub")
         .about("Manage your dofiles from a comfortable hub!")
         .subcommand_required(true)
         .arg_required_else_help(true)
         .author("Yours truly")
+        .arg(Arg::new("hub")
+            .long("hub")
+            .help("The hub to use instead of ~/.dothub, $DOTHUB_HOME does the same.")
+            .global(true))
+        .arg(Arg::new("format")
+            .long("format")
+            .help("How 'list', 'profile list' and 'status' print, 'json' and 'toml' are for scripts.")
+            .value_parser(output::FORMATS)
+            .default_value("plain")
+            .global(true))
+        .arg(Arg::new("no-daemon")
+            .long("no-daemon")
+            .help("Don't hand the command over to a running 'dothub daemon'.")
+            .global(true)
+            .action(ArgAction::SetTrue))
         .subcommand(
             Command::new("set")
                 .about("Applies a Dot.")
                 .arg(Arg::new("location")
                     .help("Dotfolder/Dot, example 'waybar/neon'.")
-                    .required(true))
+                    .required(true)
+                    .add(ArgValueCandidates::new(completions::dots)))
         )
         .subcommand(
             Command::new("watch")
                 .about("Watches a Dot and reloads on a change.")
                 .arg(Arg::new("location")
                     .help("Dotfolder/Dot, example 'waybar/neon'.")
-                    .required(true))
+                    .required(true)
+                    .add(ArgValueCandidates::new(completions::dots)))
+                .arg(Arg::new("write-back")
+                    .long("write-back")
+                    .help("What to do when a program replaces the links at the destination with real files. Overrides 'write_back' from .dothub .")
+                    .value_parser(["off", "warn", "adopt"]))
         )
         .subcommand(
             Command::new("list")
@@ -628,21 +1454,24 @@ fn arguments() -> clap::ArgMatches {
                 .about("Runs the 'start' command. DotFolder config used if Dot isn't specified, or there is no Dot config")
                 .arg(Arg::new("location")
                     .help("DotFolder/<Dot>, DotFolder has to be present but Dot can be not specified. Example 'waybar', or 'waybar/neon'.")
-                    .required(true))
+                    .required(true)
+                    .add(ArgValueCandidates::new(completions::locations)))
         )
         .subcommand(
             Command::new("kill")
                 .about("Runs the 'kill' command. DotFolder config used if Dot isn't specified, or there is no Dot config")
                 .arg(Arg::new("location")
                     .help("DotFolder/<Dot>, DotFolder has to be present but Dot can be not specified. Example 'waybar', or 'waybar/neon'.")
-                    .required(true))
+                    .required(true)
+                    .add(ArgValueCandidates::new(completions::locations)))
         )
         .subcommand(
             Command::new("reload")
                 .about("Runs the 'reload' command. DotFolder config used if Dot isn't specified, or there is no Dot config")
                 .arg(Arg::new("location")
                     .help("DotFolder/<Dot>, DotFolder has to be present but Dot can be not specified. Example 'waybar', or 'waybar/neon'.")
-                    .required(true))
+                    .required(true)
+                    .add(ArgValueCandidates::new(completions::locations)))
         )
         .subcommand(
             Command::new("run")
@@ -654,9 +1483,211 @@ fn arguments() -> clap::ArgMatches {
                 .about("Get your existing dotfiles into your .dothub. Input multiple relative or absolute paths, you'll give them all .dothub locations seperatly.")
                 .arg(Arg::new("paths")
                     .help("Relative or absolute paths to your existing dot files.")
-                    .required(true)
+                    .required_unless_present_any(["map", "restore"])
                     .num_args(0..)
                 )
+                .arg(Arg::new("to")
+                    .long("to")
+                    .help("DotFolder/Dot to put all the paths into, instead of asking for every path.")
+                )
+                .arg(Arg::new("map")
+                    .long("map")
+                    .help("TOML file of '\"path\" = \"DotFolder/Dot\"' pairs, for getting many paths at once.")
+                )
+                .arg(Arg::new("adopt")
+                    .long("adopt")
+                    .help("Move the paths into your .dothub and replace them with symlinks to it. Writes the DotFolder's .dothub if it's missing.")
+                    .action(ArgAction::SetTrue)
+                )
+                .arg(Arg::new("restore")
+                    .long("restore")
+                    .value_name("DotFolder/Dot")
+                    .help("Bring back the Dot that was last replaced by 'get', the current one is kept as well.")
+                    .conflicts_with_all(["paths", "map", "to", "adopt"])
+                )
+                .arg(Arg::new("force")
+                    .long("force")
+                    .help("Replace Dots that already exist, without asking.")
+                    .action(ArgAction::SetTrue)
+                    .conflicts_with("skip-existing")
+                )
+                .arg(Arg::new("skip-existing")
+                    .long("skip-existing")
+                    .help("Keep Dots that already exist, without asking.")
+                    .action(ArgAction::SetTrue)
+                )
+        )
+        .subcommand(
+            Command::new("dot")
+                .about("Copies, renames and deletes Dots, the profiles using them are updated.")
+                .subcommand_required(true)
+                .arg_required_else_help(true)
+                .subcommand(
+                    Command::new("cp")
+                        .about("Copies a Dot.")
+                        .arg(Arg::new("location")
+                            .help("DotFolder/Dot to copy, example 'waybar/neon'.")
+                            .required(true)
+                            .add(ArgValueCandidates::new(completions::dots)))
+                        .arg(Arg::new("to")
+                            .help("Name of the copy, or DotFolder/Dot to copy it into another DotFolder.")
+                            .required(true))
+                )
+                .subcommand(
+                    Command::new("mv")
+                        .about("Renames a Dot, the profiles using it and its links are updated.")
+                        .arg(Arg::new("location")
+                            .help("DotFolder/Dot to rename, example 'waybar/nord'.")
+                            .required(true)
+                            .add(ArgValueCandidates::new(completions::dots)))
+                        .arg(Arg::new("to")
+                            .help("The new name, example 'nord-v2'.")
+                            .required(true))
+                )
+                .subcommand(
+                    Command::new("rm")
+                        .about("Deletes a Dot.")
+                        .arg(Arg::new("location")
+                            .help("DotFolder/Dot to delete, example 'waybar/neon'.")
+                            .required(true)
+                            .add(ArgValueCandidates::new(completions::dots)))
+                        .arg(Arg::new("force")
+                            .long("force")
+                            .help("Delete it even if it's set or used by profiles, its links and profile entries are removed.")
+                            .action(ArgAction::SetTrue))
+                )
+        )
+        .subcommand(
+            Command::new("new")
+                .about("Creates a DotFolder with a commented .dothub, or a new Dot.")
+                .arg(Arg::new("location")
+                    .help("DotFolder or DotFolder/Dot, example 'waybar' or 'waybar/neon'. A missing DotFolder is created as well.")
+                    .required(true)
+                    .add(ArgValueCandidates::new(completions::dotfolders)))
+                .arg(Arg::new("destination")
+                    .long("destination")
+                    .help("Where the Dots are linked to."))
+                .arg(Arg::new("preset")
+                    .long("preset")
+                    .help("Fills in 'destination', 'start', 'kill' and 'reload' of a common program. Used on its own when the DotFolder is named like one.")
+                    .value_parser(new::preset_names()))
+                .arg(Arg::new("start")
+                    .long("start")
+                    .help("The 'start' command."))
+                .arg(Arg::new("kill")
+                    .long("kill")
+                    .help("The 'kill' command."))
+                .arg(Arg::new("reload")
+                    .long("reload")
+                    .help("The 'reload' command."))
+                .arg(Arg::new("find-and-replace")
+                    .long("find-and-replace")
+                    .help("Link the files of a Dot one by one, instead of the whole folder.")
+                    .action(ArgAction::SetTrue))
+                .arg(Arg::new("from")
+                    .long("from")
+                    .help("Dot to copy the new Dot from, of the same DotFolder or 'DotFolder/Dot'.")
+                    .add(ArgValueCandidates::new(completions::dots)))
+        )
+        .subcommand(
+            Command::new("edit")
+                .about("Opens $VISUAL or $EDITOR on a Dot, a file of it, or a DotFolder.")
+                .arg(Arg::new("location")
+                    .help("DotFolder or DotFolder/Dot, example 'waybar' or 'waybar/neon'.")
+                    .required(true)
+                    .add(ArgValueCandidates::new(completions::locations)))
+                .arg(Arg::new("file")
+                    .help("A file of the Dot, a part of its name is enough, example 'style' for 'style.css'."))
+                .arg(Arg::new("reload")
+                    .long("reload")
+                    .help("Check the .dothub and reload the Dot when the editor exits, if the Dot is set.")
+                    .action(ArgAction::SetTrue))
+        )
+        .subcommand(
+            Command::new("import")
+                .about("Converts dotfiles managed by other tools into DotFolders, with their .dothub written.")
+                .subcommand_required(true)
+                .arg_required_else_help(true)
+                .subcommand(
+                    import_command("stow", "A GNU Stow directory, every package becomes a DotFolder.")
+                        .arg(Arg::new("dotfiles")
+                            .long("dotfiles")
+                            .help("Like 'stow --dotfiles', 'dot-' prefixes become '.'.")
+                            .action(ArgAction::SetTrue))
+                )
+                .subcommand(
+                    import_command("chezmoi", "A chezmoi source directory, usually ~/.local/share/chezmoi .")
+                )
+                .subcommand(
+                    import_command("bare-git", "The git directory of a bare repository with your $HOME as its work tree.")
+                )
+        )
+        .subcommand(
+            Command::new("status")
+                .about("Shows which Dot of every DotFolder is currently set, and the programs supervised by the daemon.")
+        )
+        .subcommand(
+            Command::new("diff")
+                .about("Shows what setting a Dot would change, against the files at its destination or against another Dot.")
+                .arg(Arg::new("location")
+                    .help("Dotfolder/Dot, example 'waybar/nord'.")
+                    .required(true)
+                    .add(ArgValueCandidates::new(completions::dots)))
+                .arg(Arg::new("other")
+                    .help("Dot to compare to instead of the destination, of the same DotFolder or 'DotFolder/Dot'.")
+                    .add(ArgValueCandidates::new(completions::dots)))
+        )
+        .subcommand(
+            Command::new("pick")
+                .about("Lets a menu program (rofi, wofi, fuzzel, dmenu, fzf) choose a Dot or profile, and applies it.")
+                .arg(Arg::new("kind")
+                    .help("What to choose from.")
+                    .value_parser(pick::KINDS)
+                    .default_value("dots"))
+                .arg(Arg::new("menu")
+                    .long("menu")
+                    .help("Menu command to run instead of 'menu' of the settings, example 'wofi --dmenu'."))
+                .arg(Arg::new("icons")
+                    .long("icons")
+                    .help("Write the entries in rofi's extended format, with icons.")
+                    .action(ArgAction::SetTrue))
+        )
+        .subcommand(
+            Command::new("tui")
+                .about("Browse DotFolders, Dots and profiles, and set, watch, reload or kill them with a key.")
+        )
+        .subcommand(
+            Command::new("doctor")
+                .about("Checks every .dothub and profile, and reports everything that is wrong with them.")
+        )
+        .subcommand(
+            Command::new("schema")
+                .about("Prints a JSON Schema of a kind of file, for completion in editors.")
+                .arg(Arg::new("kind")
+                    .help("'dothub' for .dothub files, 'profile' for profiles, 'config' for config.toml, 'settings' for ~/.config/dothub/config.toml, 'schedule' for schedule.toml .")
+                    .value_parser(schema::KINDS)
+                    .default_value("dothub"))
+        )
+        .subcommand(
+            Command::new("completions")
+                .about("Prints the completion script of a shell, eg. 'source <(dothub completions bash)' in ~/.bashrc .")
+                .arg(Arg::new("shell")
+                    .value_parser(completions::SHELLS)
+                    .required(true))
+        )
+        .subcommand(
+            Command::new("daemon")
+                .about("Keeps the hub loaded, watches it for changes and supervises started programs. Other commands use it when it's running.")
+        )
+        .subcommand(
+            Command::new("schedule")
+                .about("Sets profiles automatically, following the rules in .dothub/schedule.toml")
+                .subcommand_required(true)
+                .arg_required_else_help(true)
+                .subcommand(
+                    Command::new("run")
+                        .about("Keeps running and sets profiles when their rules fire. The daemon does this on its own.")
+                )
         )
         .subcommand(
             Command::new("profile")
@@ -666,12 +1697,54 @@ fn arguments() -> clap::ArgMatches {
                 .subcommand(
                     Command::new("set")
                         .about("Sets a profile.")
-                        .arg(Arg::new("Profile").required(true))
+                        .arg(Arg::new("Profile")
+                            .required(true)
+                            .add(ArgValueCandidates::new(completions::profiles)))
                 )
                 .subcommand(
                     Command::new("list")
                         .about("Lists all DotProfiles.")
                 )
         )
-        .get_matches()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn config_toml_is_the_lowest_layer() {
+        let hub_path = env::temp_dir().join(format!("dothub-layers-{}", std::process::id()));
+
+        fs::create_dir_all(hub_path.join("waybar/neon")).unwrap();
+        fs::write(
+            hub_path.join("config.toml"),
+            "start = 'waybar'\nreload = 'pkill -USR2 waybar'\ndestination = '/hub'\n\n\
+             [notifications]\nenabled = true\n\n\
+             [[overrides]]\nwhen = { env = 'HOME' }\nkill = 'pkill waybar'\n",
+        )
+        .unwrap();
+        fs::write(
+            hub_path.join("waybar/.dothub"),
+            "destination = '/dotfolder'\nreload = 'true'\n",
+        )
+        .unwrap();
+        fs::write(hub_path.join("waybar/neon/.dothub"), "reload = 'false'\n").unwrap();
+
+        let dotfolder = process_dotfolder(&hub_path.join("waybar"));
+
+        fs::remove_dir_all(&hub_path).unwrap();
+
+        let dotfolder = dotfolder.unwrap();
+        let config = get_active_config((&dotfolder, Some(&dotfolder.dots[0]))).unwrap();
+
+        // only set in config.toml
+        assert_eq!(config.start.as_deref(), Some("waybar"));
+        assert_eq!(config.kill.as_deref(), Some("pkill waybar"));
+        assert_eq!(config.notifications.unwrap().enabled, Some(true));
+
+        // set in every layer
+        assert_eq!(config.destination, "/dotfolder");
+        assert_eq!(config.reload.as_deref(), Some("false"));
+    }
 }
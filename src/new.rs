@@ -0,0 +1,332 @@
+use std::{
+    fs,
+    path::{self, Path},
+};
+
+use anyhow::{bail, Context, Result};
+use clap::ArgMatches;
+
+use crate::get::{contract_home, copy_tree, print_skipped};
+
+// programs that have a well known place for their config and a way to reload it
+struct Preset {
+    name: &'static str,
+    destination: &'static str,
+    start: Option<&'static str>,
+    kill: Option<&'static str>,
+    reload: Option<&'static str>,
+}
+
+const PRESETS: [Preset; 10] = [
+    Preset {
+        name: "waybar",
+        destination: "~/.config/waybar",
+        start: Some("waybar"),
+        kill: Some("killall waybar"),
+        reload: Some("killall -SIGUSR2 waybar"),
+    },
+    Preset {
+        name: "polybar",
+        destination: "~/.config/polybar",
+        start: Some("polybar"),
+        kill: Some("polybar-msg cmd quit"),
+        reload: Some("polybar-msg cmd restart"),
+    },
+    Preset {
+        name: "sway",
+        destination: "~/.config/sway",
+        start: None,
+        kill: None,
+        reload: Some("swaymsg reload"),
+    },
+    Preset {
+        name: "hyprland",
+        destination: "~/.config/hypr",
+        start: None,
+        kill: None,
+        reload: Some("hyprctl reload"),
+    },
+    Preset {
+        name: "kitty",
+        destination: "~/.config/kitty",
+        start: None,
+        kill: None,
+        reload: Some("killall -SIGUSR1 kitty"),
+    },
+    // alacritty, rofi and wofi read their config again on their own
+    Preset {
+        name: "alacritty",
+        destination: "~/.config/alacritty",
+        start: None,
+        kill: None,
+        reload: None,
+    },
+    Preset {
+        name: "rofi",
+        destination: "~/.config/rofi",
+        start: None,
+        kill: None,
+        reload: None,
+    },
+    Preset {
+        name: "wofi",
+        destination: "~/.config/wofi",
+        start: None,
+        kill: None,
+        reload: None,
+    },
+    Preset {
+        name: "dunst",
+        destination: "~/.config/dunst",
+        start: Some("dunst"),
+        kill: Some("killall dunst"),
+        reload: Some("dunstctl reload"),
+    },
+    Preset {
+        name: "mako",
+        destination: "~/.config/mako",
+        start: Some("mako"),
+        kill: Some("makoctl dismiss --all; killall mako"),
+        reload: Some("makoctl reload"),
+    },
+];
+
+pub fn preset_names() -> Vec<&'static str> {
+    PRESETS.iter().map(|preset| preset.name).collect()
+}
+
+// the fields given on the command line, or taken from a preset
+#[derive(Default)]
+struct Fields {
+    destination: Option<String>,
+    start: Option<String>,
+    kill: Option<String>,
+    reload: Option<String>,
+    find_and_replace: bool,
+}
+
+pub fn new(matches: &ArgMatches, folder_path: &Path) -> Result<()> {
+    let location = matches.get_one::<String>("location").unwrap();
+
+    let (dotfolder, dot) = match location.split_once('/') {
+        Some((dotfolder, dot)) => (dotfolder, Some(dot)),
+        None => (location.as_str(), None),
+    };
+
+    check_name(dotfolder)?;
+    if dotfolder == "profiles" {
+        bail!("'profiles' is where your profiles are, it can't be a DotFolder.");
+    }
+    if let Some(dot) = dot {
+        check_name(dot)?;
+    }
+
+    let dotfolder_path = folder_path.join(dotfolder);
+    let dotfolder_config = dotfolder_path.join(".dothub");
+
+    // a new DotFolder picks a preset by its name too, when there is nothing else to go by
+    let preset = match matches.get_one::<String>("preset") {
+        Some(name) => PRESETS.iter().find(|preset| preset.name == name),
+        None if !matches.contains_id("destination") && !dotfolder_config.exists() => {
+            PRESETS.iter().find(|preset| preset.name == dotfolder)
+        }
+        None => None,
+    };
+
+    let mut fields = Fields {
+        destination: preset.map(|preset| preset.destination.to_owned()),
+        start: preset.and_then(|preset| preset.start.map(str::to_owned)),
+        kill: preset.and_then(|preset| preset.kill.map(str::to_owned)),
+        reload: preset.and_then(|preset| preset.reload.map(str::to_owned)),
+        find_and_replace: matches.get_flag("find-and-replace"),
+    };
+
+    if let Some(destination) = matches.get_one::<String>("destination") {
+        fields.destination = Some(expand_destination(destination)?);
+    }
+    for (field, name) in [
+        (&mut fields.start, "start"),
+        (&mut fields.kill, "kill"),
+        (&mut fields.reload, "reload"),
+    ] {
+        if let Some(command) = matches.get_one::<String>(name) {
+            *field = Some(command.to_owned());
+        }
+    }
+
+    let dot = match dot {
+        Some(dot) => dot,
+        None => {
+            if dotfolder_config.exists() {
+                bail!("DotFolder '{}' already exists.", dotfolder);
+            }
+
+            return new_dotfolder(&dotfolder_path, dotfolder, &fields);
+        }
+    };
+
+    let dot_path = dotfolder_path.join(dot);
+
+    if dot_path.exists() {
+        bail!("Dot '{}' already exists.", location);
+    }
+
+    let from = matches
+        .get_one::<String>("from")
+        .map(|from| {
+            // a Dot of the same DotFolder, or 'DotFolder/Dot'
+            let from_path = match from.contains('/') {
+                true => folder_path.join(from),
+                false => dotfolder_path.join(from),
+            };
+
+            match from_path.is_dir() {
+                true => Ok(from_path),
+                false => Err(anyhow::anyhow!("Dot '{}' doesn't exist.", from)),
+            }
+        })
+        .transpose()?;
+
+    // the options belong to the DotFolder when it's created as well, otherwise to the Dot
+    let dot_fields = if dotfolder_config.exists() {
+        fields
+    } else {
+        new_dotfolder(&dotfolder_path, dotfolder, &fields)?;
+        Fields::default()
+    };
+
+    let has_fields = dot_fields.destination.is_some()
+        || dot_fields.start.is_some()
+        || dot_fields.kill.is_some()
+        || dot_fields.reload.is_some()
+        || dot_fields.find_and_replace;
+
+    if has_fields
+        && from
+            .as_ref()
+            .is_some_and(|from| from.join(".dothub").exists())
+    {
+        bail!(
+            "The Dot '{}' is made from has a .dothub, copy it without options and edit it instead.",
+            location
+        );
+    }
+
+    match from {
+        Some(from_path) => {
+            let mut skipped = vec![];
+            copy_tree(&from_path, &dot_path, &mut skipped)
+                .context("Couldn't copy the Dot it's made from.")?;
+
+            print_skipped(&skipped);
+        }
+        None => fs::create_dir_all(&dot_path).context("Couldn't create the Dot.")?,
+    }
+
+    if has_fields {
+        fs::write(
+            dot_path.join(".dothub"),
+            config_text(&format!("Dot '{}'", location), &dot_fields, true),
+        )
+        .context("Couldn't write the Dot's .dothub .")?;
+    }
+
+    println!(
+        "Created Dot '{}', put your config into '{}' and run 'dothub set {}'.",
+        location,
+        dot_path.display(),
+        location
+    );
+
+    Ok(())
+}
+
+fn new_dotfolder(dotfolder_path: &Path, name: &str, fields: &Fields) -> Result<()> {
+    let destination = match &fields.destination {
+        Some(destination) => destination,
+        None => bail!(
+            "DotFolder '{}' needs a '--destination', or a '--preset' ({}).",
+            name,
+            preset_names().join(", ")
+        ),
+    };
+
+    fs::create_dir_all(dotfolder_path).context("Couldn't create the DotFolder.")?;
+    fs::write(
+        dotfolder_path.join(".dothub"),
+        config_text(&format!("DotFolder '{}'", name), fields, false),
+    )
+    .context("Couldn't write the DotFolder's .dothub .")?;
+
+    println!("Created DotFolder '{}' linking to '{}'.", name, destination);
+
+    Ok(())
+}
+
+// a .dothub with every option in it, the unused ones commented out
+fn config_text(what: &str, fields: &Fields, dot: bool) -> String {
+    let value = |value: &str| toml::Value::String(value.to_owned()).to_string();
+
+    let option = |name: &str, field: &Option<String>, example: &str| match field {
+        Some(field) => format!("{} = {}\n", name, value(field)),
+        None => format!("# {} = {}\n", name, value(example)),
+    };
+
+    let mut text = format!("# {}, written by 'dothub new'\n\n", what);
+
+    text += "# where the Dot is linked to on 'dothub set'\n";
+    text += &match (&fields.destination, dot) {
+        (Some(destination), _) => format!("destination = {}\n", value(destination)),
+        (None, true) => {
+            "# without it, the DotFolder's destination is used\n".to_owned()
+                + &option("destination", &None, "~/.config/program")
+        }
+        (None, false) => unreachable!(),
+    };
+
+    text += "\n# ran by 'dothub start', 'kill' and 'reload'\n";
+    text += &option("start", &fields.start, "program");
+    text += &option("kill", &fields.kill, "killall program");
+    text += "# without 'reload', 'kill' and 'start' are used\n";
+    text += &option("reload", &fields.reload, "killall -SIGUSR1 program");
+
+    text += "\n# run 'reload' after 'dothub set'\n";
+    text += "# reload_on_set = true\n";
+    text += "\n# link the files of the Dot one by one, instead of the whole folder\n";
+    text += match fields.find_and_replace {
+        true => "find_and_replace = true\n",
+        false => "# find_and_replace = true\n",
+    };
+    text += "\n# what 'dothub watch' does when a program replaces the links with real files\n";
+    text += "# write_back = \"warn\"\n";
+    text += "\n# link single paths of the Dot somewhere else, as many as needed\n";
+    text += "# [[links]]\n# source = \"zshrc\"\n# destination = \"~/.zshrc\"\n";
+
+    text += "\n# other values on some machines, see 'Conditions' in the README\n";
+    text += "# [[overrides]]\n# when = { hostname = \"laptop\" }\n# start = \"program --small\"\n";
+
+    text
+}
+
+// a DotFolder or Dot that 'process_hub' reads: not empty, not hidden, and inside of the hub
+pub fn check_name(name: &str) -> Result<()> {
+    if name.is_empty() || name.starts_with('.') || name.contains('/') {
+        bail!(
+            "'{}' isn't a valid name, use 'DotFolder' or 'DotFolder/Dot', without leading dots.",
+            name
+        );
+    }
+
+    Ok(())
+}
+
+// a relative destination is relative to where 'new' was run, the .dothub needs it absolute
+fn expand_destination(destination: &str) -> Result<String> {
+    if destination.starts_with('~') {
+        return Ok(destination.to_owned());
+    }
+
+    let destination = path::absolute(destination).context("Couldn't find the destination.")?;
+
+    Ok(contract_home(&destination))
+}
@@ -0,0 +1,115 @@
+use anyhow::Result;
+use notify_rust::{Notification, Urgency};
+use schemars::JsonSchema;
+use serde_derive::{Deserialize, Serialize};
+
+use crate::settings;
+
+// the '[notifications]' table, either in '.dothub/config.toml' or in a DotFolder's/Dot's .dothub
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone, PartialEq, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Notifications {
+    pub enabled: Option<bool>,
+    pub backend: Option<Backend>,
+    // run with '<shell> -c', 'shell' of the settings, gets $DOTHUB_SUMMARY, $DOTHUB_BODY and
+    // $DOTHUB_URGENCY
+    pub command: Option<String>,
+    pub on_success: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, Serialize, JsonSchema, Clone, Copy, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Backend {
+    Dbus,
+    Command,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Outcome {
+    Success,
+    Invalid,
+    Error,
+}
+
+impl Notifications {
+    // fields of 'self' take priority over the ones of 'fallback'
+    pub fn merge(&self, fallback: &Notifications) -> Notifications {
+        Notifications {
+            enabled: self.enabled.or(fallback.enabled),
+            backend: self.backend.or(fallback.backend),
+            command: self.command.clone().or_else(|| fallback.command.clone()),
+            on_success: self.on_success.or(fallback.on_success),
+        }
+    }
+
+    pub fn send(&self, outcome: Outcome, summary: &str, body: &str) {
+        // notifications are opt-in
+        if self.enabled != Some(true) {
+            return;
+        }
+
+        if outcome == Outcome::Success && self.on_success == Some(false) {
+            return;
+        }
+
+        let urgency = match outcome {
+            Outcome::Success => "normal",
+            Outcome::Invalid | Outcome::Error => "critical",
+        };
+
+        if self.backend.unwrap_or(Backend::Dbus) == Backend::Dbus {
+            let sent = Notification::new()
+                .appname("dothub")
+                .summary(summary)
+                .body(body)
+                .urgency(match outcome {
+                    Outcome::Success => Urgency::Normal,
+                    Outcome::Invalid | Outcome::Error => Urgency::Critical,
+                })
+                .show();
+
+            match sent {
+                Ok(_) => return,
+                // fall back to the command, if there is one
+                Err(e) if self.command.is_none() => {
+                    settings::warn(&format!("Couldn't send a notification over D-Bus: {}", e));
+                    return;
+                }
+                Err(_) => {}
+            }
+        }
+
+        if let Some(cmd) = &self.command {
+            let ran = settings::shell_command(cmd)
+                .env("DOTHUB_SUMMARY", summary)
+                .env("DOTHUB_BODY", body)
+                .env("DOTHUB_URGENCY", urgency)
+                .output();
+
+            if let Err(e) = ran {
+                settings::warn(&format!("Couldn't run the notification command: {}", e));
+            }
+        } else {
+            settings::warn("Notification backend is 'command', but no 'command' is specified.");
+        }
+    }
+
+    // notifies about a configuration problem, passes the result through
+    pub fn validate<T>(&self, summary: &str, result: Result<T>) -> Result<T> {
+        if let Err(e) = &result {
+            self.send(Outcome::Invalid, summary, &format!("{:#}", e));
+        }
+
+        result
+    }
+
+    // notifies about both success and failure, passes the result through
+    pub fn report<T>(&self, summary: &str, success: &str, result: Result<T>) -> Result<T> {
+        match &result {
+            Ok(_) => self.send(Outcome::Success, summary, success),
+            Err(e) => self.send(Outcome::Error, summary, &format!("{:#}", e)),
+        }
+
+        result
+    }
+}
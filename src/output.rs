@@ -0,0 +1,169 @@
+use std::collections::BTreeMap;
+
+use anyhow::{Context, Result};
+use clap::ArgMatches;
+use serde_derive::Serialize;
+
+use crate::DotConfig;
+
+pub const FORMATS: [&str; 3] = ["plain", "json", "toml"];
+
+// the global '--format', what 'list', 'profile list' and 'status' print
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    Plain,
+    Json,
+    Toml,
+}
+
+impl Format {
+    pub fn from_args(args: &ArgMatches) -> Format {
+        Format::from_name(args.get_one::<String>("format").unwrap())
+    }
+
+    pub fn from_name(name: &str) -> Format {
+        match name {
+            "json" => Format::Json,
+            "toml" => Format::Toml,
+            _ => Format::Plain,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Format::Plain => "plain",
+            Format::Json => "json",
+            Format::Toml => "toml",
+        }
+    }
+}
+
+// the output for people, the one 'plain' prints
+pub trait Plain {
+    fn plain(&self) -> String;
+}
+
+pub fn render<T: serde::Serialize + Plain>(value: &T, format: Format) -> Result<String> {
+    match format {
+        Format::Plain => Ok(value.plain()),
+        Format::Json => {
+            Ok(serde_json::to_string_pretty(value).context("Couldn't write JSON.")? + "\n")
+        }
+        // through a 'Value', so tables end up after the plain values
+        Format::Toml => {
+            let value = toml::Value::try_from(value).context("Couldn't write TOML.")?;
+            toml::to_string(&value).context("Couldn't write TOML.")
+        }
+    }
+}
+
+// 'dothub list'
+#[derive(Debug, Serialize)]
+pub struct List {
+    pub dotfolders: Vec<ListDotFolder>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ListDotFolder {
+    pub name: String,
+    pub dots: Vec<ListDot>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ListDot {
+    pub name: String,
+    pub active: bool,
+    // the config with every layer and override, 'error' says why there is none
+    pub config: Option<DotConfig>,
+    pub error: Option<String>,
+}
+
+impl Plain for List {
+    fn plain(&self) -> String {
+        let mut text = String::new();
+
+        for df in &self.dotfolders {
+            text += &format!("{}/\n", df.name);
+
+            for d in &df.dots {
+                text += &format!("  {}\n", d.name);
+            }
+        }
+
+        text
+    }
+}
+
+// 'dothub profile list', with the [[overrides]] that hold applied
+#[derive(Debug, Serialize)]
+pub struct Profiles {
+    pub profiles: Vec<ListProfile>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ListProfile {
+    pub name: String,
+    pub start: Vec<String>,
+    pub dots: BTreeMap<String, String>,
+}
+
+impl Plain for Profiles {
+    fn plain(&self) -> String {
+        self.profiles
+            .iter()
+            .map(|profile| format!("{}\n", profile.name))
+            .collect()
+    }
+}
+
+// 'dothub status', 'supervised' is only filled in by the daemon
+#[derive(Debug, Serialize)]
+pub struct Status {
+    pub dotfolders: Vec<StatusDotFolder>,
+    pub supervised: Vec<Supervised>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct StatusDotFolder {
+    pub name: String,
+    pub active: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Supervised {
+    pub location: String,
+    pub running: bool,
+    pub pid: Option<u32>,
+    // how it exited, when it isn't running anymore
+    pub exit: Option<String>,
+}
+
+impl Plain for Status {
+    fn plain(&self) -> String {
+        let mut text = String::new();
+
+        for df in &self.dotfolders {
+            match &df.active {
+                Some(dot) => text += &format!("{}/{}\n", df.name, dot),
+                None => text += &format!("{}/ -\n", df.name),
+            }
+        }
+
+        if self.supervised.is_empty() {
+            return text;
+        }
+
+        text += "\nsupervised:\n";
+
+        for program in &self.supervised {
+            let state = match (&program.exit, program.pid) {
+                (Some(exit), _) => exit.to_owned(),
+                (None, pid) => format!("running, pid {}", pid.unwrap_or_default()),
+            };
+
+            text += &format!("  {}  {}\n", program.location, state);
+        }
+
+        text
+    }
+}
@@ -0,0 +1,144 @@
+use std::{
+    io::Write,
+    process::{ExitStatus, Stdio},
+    thread,
+};
+
+use anyhow::{bail, Context, Result};
+use clap::ArgMatches;
+
+use crate::{daemon, doctor::on_path, settings, Hub};
+
+pub const KINDS: [&str; 2] = ["dots", "profiles"];
+
+// the first one on $PATH is used when 'menu' isn't set
+const MENUS: [(&str, &str); 5] = [
+    ("rofi", "rofi -dmenu -i -p dothub"),
+    ("wofi", "wofi --dmenu -i -p dothub"),
+    ("fuzzel", "fuzzel --dmenu"),
+    ("dmenu", "dmenu -i -p dothub"),
+    ("fzf", "fzf --prompt 'dothub> '"),
+];
+
+// what the Dot that is set ends with in the menu
+const ACTIVE: &str = " *";
+
+struct Entry {
+    text: String,
+    // an icon name of the icon theme, a Dot gets the one of its DotFolder's program
+    icon: String,
+    active: bool,
+}
+
+// 'dothub pick', lets a menu program choose a Dot or profile and applies it
+pub fn pick(matches: &ArgMatches, hub: &Hub, use_daemon: bool) -> Result<()> {
+    let kind = matches.get_one::<String>("kind").unwrap();
+    let icons = matches.get_flag("icons") || settings::get().menu_icons.unwrap_or(false);
+
+    let menu = match matches.get_one::<String>("menu") {
+        Some(menu) => menu.to_owned(),
+        None => menu(icons)?,
+    };
+
+    let entries: Vec<Entry> = match kind.as_str() {
+        "profiles" => hub
+            .profiles
+            .iter()
+            .map(|profile| Entry {
+                text: profile.name.clone(),
+                icon: "preferences-desktop-theme".to_owned(),
+                active: false,
+            })
+            .collect(),
+        _ => hub
+            .dot_folders
+            .iter()
+            .flat_map(|df| {
+                df.dots.iter().map(move |d| Entry {
+                    text: format!("{}/{}", df.name, d.name),
+                    icon: df.name.clone(),
+                    active: hub.is_active(df, d),
+                })
+            })
+            .collect(),
+    };
+
+    let input: String = entries
+        .iter()
+        .map(|entry| {
+            let mut line = entry.text.clone();
+
+            if entry.active {
+                line += ACTIVE;
+            }
+
+            // rofi's extended format
+            if icons {
+                line += &format!("\0icon\x1f{}", entry.icon);
+            }
+
+            line + "\n"
+        })
+        .collect();
+
+    let (status, chosen) = run_menu(&menu, input)?;
+
+    // closed without choosing anything
+    if !status.success() || chosen.trim().is_empty() {
+        return Ok(());
+    }
+
+    let chosen = chosen.lines().next().unwrap_or_default();
+    let chosen = chosen.strip_suffix(ACTIVE).unwrap_or(chosen);
+
+    if !entries.iter().any(|entry| entry.text == chosen) {
+        bail!("'{}' isn't one of the {}.", chosen, kind);
+    }
+
+    match kind.as_str() {
+        "profiles" => daemon::send_or(hub, use_daemon, &["profile-set", chosen], |hub| {
+            hub.profile_set(chosen)
+        }),
+        _ => daemon::send_or(hub, use_daemon, &["set", chosen], |hub| hub.set(chosen)),
+    }
+}
+
+// 'menu' of the settings, or the first menu program that is installed
+fn menu(icons: bool) -> Result<String> {
+    if let Some(menu) = &settings::get().menu {
+        return Ok(menu.to_owned());
+    }
+
+    let (program, menu) = MENUS
+        .iter()
+        .find(|(program, _)| on_path(program))
+        .context("No menu program found, install rofi, wofi, fuzzel, dmenu or fzf, or set 'menu' in the settings.")?;
+
+    match (*program, icons) {
+        ("rofi", true) => Ok(menu.to_string() + " -show-icons"),
+        _ => Ok(menu.to_string()),
+    }
+}
+
+// the entries go to stdin, the chosen one comes from stdout
+fn run_menu(menu: &str, input: String) -> Result<(ExitStatus, String)> {
+    let mut child = settings::shell_command(menu)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Couldn't run '{}'.", menu))?;
+
+    // a menu that exits before reading everything mustn't block us
+    let mut stdin = child.stdin.take().unwrap();
+    let writer = thread::spawn(move || stdin.write_all(input.as_bytes()));
+
+    let output = child
+        .wait_with_output()
+        .with_context(|| format!("Couldn't run '{}'.", menu))?;
+    let _ = writer.join();
+
+    Ok((
+        output.status,
+        String::from_utf8_lossy(&output.stdout).into_owned(),
+    ))
+}
@@ -0,0 +1,673 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Stdio,
+    sync::mpsc::{self, TryRecvError},
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+
+use anyhow::{bail, Context, Result};
+use chrono::{Datelike, Local, NaiveDate, NaiveDateTime, TimeZone, Timelike, Utc};
+use schemars::JsonSchema;
+use serde_derive::Deserialize;
+
+use crate::{schema, settings, Hub};
+
+const CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+// .dothub/schedule.toml
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct ScheduleParsable {
+    latitude: Option<f64>,
+    longitude: Option<f64>,
+    // seconds between running the 'check' commands
+    interval: Option<u64>,
+    #[serde(default, rename = "rule")]
+    rules: Vec<RuleParsable>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema)]
+#[serde(deny_unknown_fields)]
+pub struct RuleParsable {
+    profile: String,
+    // a cron expression, 'sunrise' or 'sunset'
+    at: Option<String>,
+    // minutes added to 'sunrise'/'sunset'
+    offset: Option<i64>,
+    check: Option<String>,
+    // profile to set once 'check' stops succeeding
+    otherwise: Option<String>,
+}
+
+struct Schedule {
+    coordinates: Option<(f64, f64)>,
+    interval: Duration,
+    rules: Vec<Rule>,
+}
+
+struct Rule {
+    profile: String,
+    trigger: Trigger,
+}
+
+enum Trigger {
+    Cron(Cron),
+    Sun {
+        rising: bool,
+        offset: i64,
+    },
+    Check {
+        command: String,
+        otherwise: Option<String>,
+    },
+}
+
+// minute, hour, day of month, month, day of week
+struct Cron {
+    fields: [Vec<u32>; 5],
+    any_day_of_month: bool,
+    any_day_of_week: bool,
+}
+
+// runs 'profile set' whenever a rule from schedule.toml fires
+pub struct Scheduler {
+    hub_path: PathBuf,
+    modified: Option<SystemTime>,
+    schedule: Option<Schedule>,
+    last_minute: Option<NaiveDateTime>,
+    last_check: Option<Instant>,
+    check_states: Vec<Option<bool>>,
+    // the results of the 'check' commands that are running, by rule
+    checks: Option<mpsc::Receiver<Vec<(usize, bool)>>>,
+}
+
+impl Scheduler {
+    pub fn new(hub_path: &Path) -> Scheduler {
+        Scheduler {
+            hub_path: hub_path.to_path_buf(),
+            modified: None,
+            schedule: None,
+            last_minute: None,
+            last_check: None,
+            check_states: vec![],
+            checks: None,
+        }
+    }
+
+    fn schedule_path(&self) -> PathBuf {
+        self.hub_path.join("schedule.toml")
+    }
+
+    // 'dothub schedule run'
+    pub fn run(&mut self) -> Result<()> {
+        if !self.schedule_path().is_file() {
+            bail!("There is no 'schedule.toml' in your .dothub .");
+        }
+
+        // fail early on a broken schedule, later on it's only reported
+        process_schedule(&self.schedule_path())?;
+
+        println!(
+            "Running the schedule from '{}'.",
+            self.schedule_path().display()
+        );
+
+        loop {
+            self.tick();
+            thread::sleep(Duration::from_secs(1));
+        }
+    }
+
+    pub fn tick(&mut self) {
+        self.reload();
+
+        let now = truncate_to_minute(Local::now().naive_local());
+
+        let mut to_set: Vec<String> = self.fired(now).into_iter().collect();
+        to_set.extend(self.checked());
+
+        for profile in to_set {
+            println!("Schedule: setting profile '{}'.", profile);
+
+            if let Err(e) = Hub::load(&self.hub_path).and_then(|hub| hub.profile_set(&profile)) {
+                println!("{:?}", e);
+            }
+        }
+    }
+
+    // the profile of the last time rule that fired since the last tick
+    fn fired(&mut self, now: NaiveDateTime) -> Option<String> {
+        let schedule = self.schedule.as_ref()?;
+
+        // the first tick starts now, a restarted daemon mustn't fire the rules of the past again
+        let since = match self.last_minute {
+            Some(last_minute) => last_minute,
+            None => {
+                self.last_minute = Some(now);
+                return None;
+            }
+        };
+
+        let mut to_set = None;
+        let mut minute = since + chrono::Duration::minutes(1);
+
+        while minute <= now {
+            for rule in &schedule.rules {
+                if rule.fires_at(minute, schedule.coordinates) {
+                    to_set = Some(rule.profile.clone());
+                }
+            }
+            minute += chrono::Duration::minutes(1);
+        }
+
+        // when the clock goes back (daylight saving time ends) the repeated minutes already fired
+        self.last_minute = Some(now.max(since));
+
+        to_set
+    }
+
+    // starts the 'check' commands every 'interval' in the background, so they don't hold up the
+    // daemon, and the profiles of the ones that changed once they are done
+    fn checked(&mut self) -> Vec<String> {
+        let schedule = match &self.schedule {
+            Some(schedule) => schedule,
+            None => return vec![],
+        };
+
+        let results = match self.checks.as_ref().map(|checks| checks.try_recv()) {
+            Some(Ok(results)) => {
+                self.checks = None;
+                results
+            }
+            Some(Err(TryRecvError::Disconnected)) => {
+                self.checks = None;
+                vec![]
+            }
+            _ => vec![],
+        };
+
+        if self.checks.is_none()
+            && self
+                .last_check
+                .is_none_or(|last| last.elapsed() >= schedule.interval)
+        {
+            self.last_check = Some(Instant::now());
+
+            let commands: Vec<(usize, String)> = schedule
+                .rules
+                .iter()
+                .enumerate()
+                .filter_map(|(i, rule)| match &rule.trigger {
+                    Trigger::Check { command, .. } => Some((i, command.clone())),
+                    _ => None,
+                })
+                .collect();
+
+            if !commands.is_empty() {
+                let (tx, rx) = mpsc::channel();
+
+                thread::spawn(move || {
+                    let _ = tx.send(
+                        commands
+                            .into_iter()
+                            .map(|(i, command)| (i, check_passes(&command)))
+                            .collect(),
+                    );
+                });
+
+                self.checks = Some(rx);
+            }
+        }
+
+        self.check_states.resize(schedule.rules.len(), None);
+
+        let mut to_set = vec![];
+
+        for (i, passed) in results {
+            let otherwise = match &schedule.rules[i].trigger {
+                Trigger::Check { otherwise, .. } => otherwise,
+                _ => continue,
+            };
+
+            match (self.check_states[i], passed) {
+                (Some(true), true) | (Some(false), false) => {}
+                (_, true) => to_set.push(schedule.rules[i].profile.clone()),
+                (Some(true), false) => to_set.extend(otherwise.clone()),
+                (None, false) => {}
+            }
+
+            self.check_states[i] = Some(passed);
+        }
+
+        to_set
+    }
+
+    // parse schedule.toml again when it changed, keep the old one if it's broken
+    fn reload(&mut self) {
+        let path = self.schedule_path();
+        let modified = fs::metadata(&path).and_then(|meta| meta.modified()).ok();
+
+        if modified == self.modified {
+            return;
+        }
+
+        self.modified = modified;
+
+        if modified.is_none() {
+            self.schedule = None;
+            return;
+        }
+
+        match process_schedule(&path) {
+            Ok(schedule) => {
+                self.schedule = Some(schedule);
+                self.check_states.clear();
+                self.last_check = None;
+                // the rules they were started for are gone
+                self.checks = None;
+            }
+            Err(e) => println!("Couldn't parse the changed schedule.toml: {:?}", e),
+        }
+    }
+}
+
+impl Rule {
+    fn fires_at(&self, minute: NaiveDateTime, coordinates: Option<(f64, f64)>) -> bool {
+        match &self.trigger {
+            Trigger::Cron(cron) => cron.matches(minute),
+            Trigger::Sun { rising, offset } => {
+                let (latitude, longitude) = match coordinates {
+                    Some(coordinates) => coordinates,
+                    None => return false,
+                };
+
+                match sun_time(minute.date(), latitude, longitude, *rising) {
+                    Some(time) => time + chrono::Duration::minutes(*offset) == minute,
+                    None => false,
+                }
+            }
+            Trigger::Check { .. } => false,
+        }
+    }
+}
+
+impl Cron {
+    fn parse(expression: &str) -> Result<Cron> {
+        let parts: Vec<&str> = expression.split_whitespace().collect();
+
+        if parts.len() != 5 {
+            bail!(
+                "'{}' has to have 5 fields: minute, hour, day of month, month and day of week.",
+                expression
+            );
+        }
+
+        let ranges = [(0, 59), (0, 23), (1, 31), (1, 12), (0, 7)];
+        let mut fields: [Vec<u32>; 5] = Default::default();
+
+        for (i, (part, (min, max))) in parts.iter().zip(ranges).enumerate() {
+            fields[i] = parse_cron_field(part, min, max)
+                .with_context(|| format!("Couldn't parse '{}' in '{}'.", part, expression))?;
+        }
+
+        // 7 is sunday as well
+        if fields[4].contains(&7) {
+            fields[4].push(0);
+        }
+
+        // like cron, '*/2' counts as '*' for the days
+        Ok(Cron {
+            fields,
+            any_day_of_month: parts[2].starts_with('*'),
+            any_day_of_week: parts[4].starts_with('*'),
+        })
+    }
+
+    fn matches(&self, time: NaiveDateTime) -> bool {
+        let day_of_month = self.fields[2].contains(&time.day());
+        let day_of_week = self.fields[4].contains(&time.weekday().num_days_from_sunday());
+
+        // like cron, if both days are restricted, either of them is enough
+        let day = match (self.any_day_of_month, self.any_day_of_week) {
+            (false, false) => day_of_month || day_of_week,
+            _ => day_of_month && day_of_week,
+        };
+
+        day && self.fields[0].contains(&time.minute())
+            && self.fields[1].contains(&time.hour())
+            && self.fields[3].contains(&time.month())
+    }
+}
+
+// '*', '5', '1-5', '*/15', '0-30/10' and lists of them, '1,15'
+fn parse_cron_field(field: &str, min: u32, max: u32) -> Result<Vec<u32>> {
+    let mut values = vec![];
+
+    for item in field.split(',') {
+        let (range, step) = match item.split_once('/') {
+            Some((range, step)) => (range, step.parse::<u32>().context("Invalid step.")?),
+            None => (item, 1),
+        };
+
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((start, end)) => (start.parse()?, end.parse()?),
+                None => {
+                    let value = range.parse()?;
+                    (value, value)
+                }
+            },
+        };
+
+        if start < min || end > max || start > end || step == 0 {
+            bail!("Values have to be between {} and {}.", min, max);
+        }
+
+        values.extend((start..=end).step_by(step as usize));
+    }
+
+    Ok(values)
+}
+
+// local sunrise/sunset, to the minute, 'None' during polar days and nights
+fn sun_time(date: NaiveDate, latitude: f64, longitude: f64, rising: bool) -> Option<NaiveDateTime> {
+    // https://edwilliams.org/sunrise_sunset_algorithm.htm
+    let zenith: f64 = 90.833;
+    let lng_hour = longitude / 15.0;
+
+    let t = date.ordinal() as f64 + ((if rising { 6.0 } else { 18.0 }) - lng_hour) / 24.0;
+    let m = 0.9856 * t - 3.289;
+
+    let l = (m + 1.916 * m.to_radians().sin() + 0.020 * (2.0 * m).to_radians().sin() + 282.634)
+        .rem_euclid(360.0);
+
+    let mut ra = (0.91764 * l.to_radians().tan())
+        .atan()
+        .to_degrees()
+        .rem_euclid(360.0);
+    ra += (l / 90.0).floor() * 90.0 - (ra / 90.0).floor() * 90.0;
+    ra /= 15.0;
+
+    let sin_dec = 0.39782 * l.to_radians().sin();
+    let cos_dec = sin_dec.asin().cos();
+
+    let cos_h = (zenith.to_radians().cos() - sin_dec * latitude.to_radians().sin())
+        / (cos_dec * latitude.to_radians().cos());
+
+    if !(-1.0..=1.0).contains(&cos_h) {
+        return None;
+    }
+
+    let h = if rising {
+        360.0 - cos_h.acos().to_degrees()
+    } else {
+        cos_h.acos().to_degrees()
+    } / 15.0;
+
+    // the local mean time keeps the day, in UT a western sunset is already the next one
+    let local_mean = (h + ra - 0.06571 * t - 6.622).rem_euclid(24.0);
+    let ut = local_mean - lng_hour;
+
+    let midnight = Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0)?);
+    let time = midnight + chrono::Duration::seconds((ut * 3600.0) as i64);
+
+    Some(truncate_to_minute(time.with_timezone(&Local).naive_local()))
+}
+
+// a check that doesn't finish in time failed
+fn check_passes(command: &str) -> bool {
+    let mut child = match settings::shell_command(command)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(_) => return false,
+    };
+
+    let started = Instant::now();
+
+    loop {
+        match child.try_wait() {
+            Ok(Some(status)) => return status.success(),
+            Ok(None) if started.elapsed() < CHECK_TIMEOUT => {
+                thread::sleep(Duration::from_millis(50))
+            }
+            _ => {
+                println!("Schedule: '{}' didn't finish, killing it.", command);
+                let _ = child.kill();
+                let _ = child.wait();
+                return false;
+            }
+        }
+    }
+}
+
+fn truncate_to_minute(time: NaiveDateTime) -> NaiveDateTime {
+    time.with_second(0)
+        .and_then(|time| time.with_nanosecond(0))
+        .unwrap_or(time)
+}
+
+fn process_schedule(path: &Path) -> Result<Schedule> {
+    let contents = fs::read_to_string(path).context("Couldn't read schedule.toml .")?;
+
+    let parsed: ScheduleParsable = schema::parse(path, &contents)?;
+
+    let coordinates = match (parsed.latitude, parsed.longitude) {
+        (Some(latitude), Some(longitude)) => Some((latitude, longitude)),
+        (None, None) => None,
+        _ => bail!("schedule.toml needs both 'latitude' and 'longitude'."),
+    };
+
+    let mut rules = vec![];
+
+    for rule in parsed.rules {
+        let trigger = match (rule.at, rule.check) {
+            (Some(at), None) => match at.as_str() {
+                "sunrise" | "sunset" => {
+                    if coordinates.is_none() {
+                        bail!(
+                            "Rule for '{}' uses '{}', that needs 'latitude' and 'longitude'.",
+                            rule.profile,
+                            at
+                        );
+                    }
+
+                    Trigger::Sun {
+                        rising: at == "sunrise",
+                        offset: rule.offset.unwrap_or(0),
+                    }
+                }
+                _ => Trigger::Cron(
+                    Cron::parse(&at)
+                        .with_context(|| format!("Invalid 'at' of rule for '{}'.", rule.profile))?,
+                ),
+            },
+            (None, Some(command)) => Trigger::Check {
+                command,
+                otherwise: rule.otherwise,
+            },
+            _ => bail!(
+                "Rule for '{}' has to have either 'at' or 'check'.",
+                rule.profile
+            ),
+        };
+
+        rules.push(Rule {
+            profile: rule.profile,
+            trigger,
+        });
+    }
+
+    Ok(Schedule {
+        coordinates,
+        interval: Duration::from_secs(parsed.interval.unwrap_or(30)),
+        rules,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(date: &str, time: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(&format!("{} {}", date, time), "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    // the local time of a UTC one, 'sun_time' answers in local time
+    fn local(date: &str, time: &str) -> NaiveDateTime {
+        Utc.from_utc_datetime(&at(date, time))
+            .with_timezone(&Local)
+            .naive_local()
+    }
+
+    fn scheduler(at: &str) -> Scheduler {
+        let mut scheduler = Scheduler::new(Path::new(""));
+
+        scheduler.schedule = Some(Schedule {
+            coordinates: None,
+            interval: Duration::from_secs(30),
+            rules: vec![Rule {
+                profile: "night".to_owned(),
+                trigger: Trigger::Cron(Cron::parse(at).unwrap()),
+            }],
+        });
+
+        scheduler
+    }
+
+    #[test]
+    fn first_tick_doesnt_catch_up() {
+        let mut scheduler = scheduler("0 1 * * *");
+
+        assert_eq!(scheduler.fired(at("2024-10-27", "01:30")), None);
+        assert_eq!(scheduler.fired(at("2024-10-27", "01:31")), None);
+        assert_eq!(
+            scheduler.fired(at("2024-10-28", "01:00")),
+            Some("night".to_owned())
+        );
+    }
+
+    #[test]
+    fn repeated_minutes_dont_fire_twice() {
+        let mut scheduler = scheduler("30 2 * * *");
+
+        // daylight saving time ends at 03:00, it's 02:00 again
+        assert_eq!(scheduler.fired(at("2024-10-27", "02:00")), None);
+        assert_eq!(
+            scheduler.fired(at("2024-10-27", "02:30")),
+            Some("night".to_owned())
+        );
+        assert_eq!(scheduler.fired(at("2024-10-27", "02:59")), None);
+        assert_eq!(scheduler.fired(at("2024-10-27", "02:00")), None);
+        assert_eq!(scheduler.fired(at("2024-10-27", "02:30")), None);
+        assert_eq!(scheduler.fired(at("2024-10-27", "03:00")), None);
+    }
+
+    #[test]
+    fn cron_fields() {
+        assert_eq!(parse_cron_field("*/15", 0, 59).unwrap(), [0, 15, 30, 45]);
+        assert_eq!(parse_cron_field("1-5", 0, 7).unwrap(), [1, 2, 3, 4, 5]);
+        assert_eq!(parse_cron_field("1,15", 1, 31).unwrap(), [1, 15]);
+        assert_eq!(parse_cron_field("0-30/10", 0, 59).unwrap(), [0, 10, 20, 30]);
+        assert_eq!(parse_cron_field("*", 1, 12).unwrap().len(), 12);
+
+        assert!(parse_cron_field("60", 0, 59).is_err());
+        assert!(parse_cron_field("0", 1, 31).is_err());
+        assert!(parse_cron_field("5-1", 0, 59).is_err());
+        assert!(parse_cron_field("*/0", 0, 59).is_err());
+        assert!(parse_cron_field("a", 0, 59).is_err());
+    }
+
+    #[test]
+    fn cron_needs_five_fields() {
+        assert!(Cron::parse("0 7 * *").is_err());
+        assert!(Cron::parse("0 7 * * * *").is_err());
+    }
+
+    #[test]
+    fn cron_matches() {
+        let cron = Cron::parse("30 7 * * 1-5").unwrap();
+
+        // 2024-06-03 is a monday, 2024-06-08 a saturday
+        assert!(cron.matches(at("2024-06-03", "07:30")));
+        assert!(!cron.matches(at("2024-06-03", "07:31")));
+        assert!(!cron.matches(at("2024-06-08", "07:30")));
+
+        // 7 is sunday as well
+        let cron = Cron::parse("0 9 * * 7").unwrap();
+        assert!(cron.matches(at("2024-06-09", "09:00")));
+    }
+
+    #[test]
+    fn cron_days_with_a_step_are_anded() {
+        let cron = Cron::parse("0 7 */2 * 1").unwrap();
+
+        // 2024-06-03 is a monday on an odd day, 2024-06-05 a wednesday
+        assert!(cron.matches(at("2024-06-03", "07:00")));
+        assert!(!cron.matches(at("2024-06-05", "07:00")));
+        assert!(!cron.matches(at("2024-06-10", "07:00")));
+    }
+
+    #[test]
+    fn cron_restricted_days_are_ored() {
+        let cron = Cron::parse("0 7 1 * 1").unwrap();
+
+        // the 1st, a saturday, and mondays
+        assert!(cron.matches(at("2024-06-01", "07:00")));
+        assert!(cron.matches(at("2024-06-10", "07:00")));
+        assert!(!cron.matches(at("2024-06-11", "07:00")));
+    }
+
+    fn close(time: Option<NaiveDateTime>, expected: NaiveDateTime) -> bool {
+        time.is_some_and(|time| (time - expected).num_minutes().abs() <= 3)
+    }
+
+    #[test]
+    fn sun_times() {
+        let solstice = NaiveDate::from_ymd_opt(2024, 6, 21).unwrap();
+
+        // London, 03:43 and 20:21 UTC
+        assert!(close(
+            sun_time(solstice, 51.5074, -0.1278, true),
+            local("2024-06-21", "03:43")
+        ));
+        assert!(close(
+            sun_time(solstice, 51.5074, -0.1278, false),
+            local("2024-06-21", "20:21")
+        ));
+
+        // New York, 09:25 and 00:31 of the next day UTC
+        assert!(close(
+            sun_time(solstice, 40.7128, -74.006, true),
+            local("2024-06-21", "09:25")
+        ));
+        assert!(close(
+            sun_time(solstice, 40.7128, -74.006, false),
+            local("2024-06-22", "00:31")
+        ));
+
+        // Tokyo, 19:25 of the day before and 10:00 UTC
+        assert!(close(
+            sun_time(solstice, 35.6762, 139.6503, true),
+            local("2024-06-20", "19:25")
+        ));
+        assert!(close(
+            sun_time(solstice, 35.6762, 139.6503, false),
+            local("2024-06-21", "10:00")
+        ));
+    }
+
+    #[test]
+    fn no_sun_times_in_polar_days_and_nights() {
+        let summer = NaiveDate::from_ymd_opt(2024, 6, 21).unwrap();
+        let winter = NaiveDate::from_ymd_opt(2024, 12, 21).unwrap();
+
+        assert_eq!(sun_time(summer, 80.0, 15.0, true), None);
+        assert_eq!(sun_time(winter, 80.0, 15.0, false), None);
+    }
+}
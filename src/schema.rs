@@ -0,0 +1,203 @@
+use std::path::Path;
+
+use anyhow::{bail, Result};
+use schemars::schema_for;
+use serde::de::DeserializeOwned;
+
+use crate::{
+    get::contract_home, schedule::ScheduleParsable, settings::Settings, PartialConfig,
+    ProfileParsable,
+};
+
+pub const KINDS: [&str; 5] = ["dothub", "profile", "config", "settings", "schedule"];
+
+// 'dothub schema', a JSON Schema of a kind of file for editors
+pub fn schema(kind: &str) -> String {
+    let schema = match kind {
+        "profile" => schema_for!(ProfileParsable),
+        "settings" => schema_for!(Settings),
+        "schedule" => schema_for!(ScheduleParsable),
+        _ => schema_for!(PartialConfig),
+    };
+
+    format!("{:#}", schema.as_value())
+}
+
+// a .dothub or config.toml, the paths in them can start with '~'
+pub fn parse_dothub(path: &Path, contents: &str) -> Result<PartialConfig> {
+    parse::<PartialConfig>(path, contents).map(PartialConfig::expand_home)
+}
+
+// a profile, config.toml, schedule.toml or the settings
+pub fn parse<T: DeserializeOwned>(path: &Path, contents: &str) -> Result<T> {
+    let error = match toml::from_str(contents) {
+        Ok(parsed) => return Ok(parsed),
+        Err(error) => error,
+    };
+
+    // errors point at the line, and suggest a known key when a key is misspelled
+    let mut message = error.to_string();
+
+    // the location is shown below
+    if let Some(at) = message.find(" at line ") {
+        message.truncate(at);
+    }
+
+    let unknown = unknown(&message);
+
+    if let Some((name, known)) = &unknown {
+        if let Some(suggestion) = suggestion(name, known) {
+            // the list of every known key isn't needed anymore
+            let key = message
+                .find(" for key `")
+                .map(|start| message[start..].to_owned())
+                .unwrap_or_default();
+
+            message = format!(
+                "{}{}, did you mean `{}`?",
+                &message[..message.find(", expected").unwrap()],
+                key,
+                suggestion
+            );
+        }
+    }
+
+    let location = contract_home(path);
+
+    let (mut line, mut column) = match error.line_col() {
+        Some(line_col) => line_col,
+        None => bail!("{}\n --> {}", message, location),
+    };
+
+    // toml points at the table the unknown key is in, not at the key
+    if let Some((name, _)) = &unknown {
+        if let Some(found) = find_word(contents, line, name) {
+            (line, column) = found;
+        }
+    }
+
+    let text = contents.lines().nth(line).unwrap_or_default();
+    let column = text
+        .get(..column)
+        .map_or(column, |start| start.chars().count());
+    let number = (line + 1).to_string();
+    let margin = " ".repeat(number.len());
+
+    bail!(
+        "{}\n{}--> {}:{}:{}\n{} |\n{} | {}\n{} | {}^",
+        message,
+        margin,
+        location,
+        line + 1,
+        column + 1,
+        margin,
+        number,
+        text,
+        margin,
+        " ".repeat(column)
+    )
+}
+
+// "unknown field `destiantion`, expected one of `start`, ..." has every known key in it
+fn unknown(message: &str) -> Option<(String, Vec<String>)> {
+    if !message.starts_with("unknown field") && !message.starts_with("unknown variant") {
+        return None;
+    }
+
+    // 'for key `...`' at the end isn't one of them
+    let message = match message.find(" for key `") {
+        Some(end) => &message[..end],
+        None => message,
+    };
+
+    let mut quoted = message.split('`').skip(1).step_by(2).map(str::to_owned);
+    let name = quoted.next()?;
+
+    Some((name, quoted.collect()))
+}
+
+// the closest known key, if it's close enough
+fn suggestion(name: &str, known: &[String]) -> Option<String> {
+    known
+        .iter()
+        .map(|known| (distance(name, known), known))
+        .filter(|(distance, known)| *distance <= 2.max(known.len() / 3))
+        .min_by_key(|(distance, _)| *distance)
+        .map(|(_, known)| known.to_owned())
+}
+
+// the edit distance between two keys
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+
+        previous = current;
+    }
+
+    previous[b.len()]
+}
+
+// line and column of the first 'word' as a whole, from the given line on
+fn find_word(contents: &str, from: usize, word: &str) -> Option<(usize, usize)> {
+    let part_of_word = |c: char| c.is_alphanumeric() || c == '_' || c == '-';
+
+    for (line, text) in contents.lines().enumerate().skip(from) {
+        for (column, _) in text.match_indices(word) {
+            let before = text[..column].chars().next_back();
+            let after = text[column + word.len()..].chars().next();
+
+            if !before.is_some_and(part_of_word) && !after.is_some_and(part_of_word) {
+                return Some((line, column));
+            }
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_leading_tildes_of_paths_are_expanded() {
+        let home = std::env::var("HOME").unwrap();
+        let contents = "start = 'waybar -c ~/bar'\ndestination = '~/.config/waybar'\n\n\
+            [[links]]\nsource = '~x'\ndestination = '~'\n\n\
+            [[overrides]]\nwhen = { hostname = 'a~b' }\ndestination = '~user/waybar'\n";
+
+        let config = parse_dothub(Path::new(".dothub"), contents).unwrap();
+        let links = config.links.unwrap();
+        let overrides = config.overrides.unwrap();
+
+        assert_eq!(config.start.unwrap(), "waybar -c ~/bar");
+        assert_eq!(
+            config.destination.unwrap(),
+            home.clone() + "/.config/waybar"
+        );
+        assert_eq!(links[0].source, "~x");
+        assert_eq!(links[0].destination, home);
+        assert_eq!(overrides[0].destination.as_deref(), Some("~user/waybar"));
+    }
+
+    #[test]
+    fn errors_point_at_the_file_as_written() {
+        let contents = "start = '~/a ~/b'\ndestiantion = '~/x'\n";
+
+        let error = parse_dothub(Path::new(".dothub"), contents)
+            .unwrap_err()
+            .to_string();
+
+        assert!(error.contains("did you mean `destination`?"), "{}", error);
+        assert!(error.contains(".dothub:2:1"), "{}", error);
+        assert!(error.contains("2 | destiantion = '~/x'"), "{}", error);
+    }
+}
@@ -0,0 +1,126 @@
+use std::{
+    env, fs,
+    path::{self, Path, PathBuf},
+    process,
+    sync::OnceLock,
+};
+
+use anyhow::{Context, Result};
+use schemars::JsonSchema;
+use serde_derive::Deserialize;
+
+use crate::{get::expand_home, schema};
+
+static SETTINGS: OnceLock<Settings> = OnceLock::new();
+
+// ~/.config/dothub/config.toml, settings of dothub itself instead of a hub
+#[derive(Debug, Deserialize, JsonSchema, Default)]
+#[serde(deny_unknown_fields)]
+pub struct Settings {
+    // where the hub is, '--hub' and $DOTHUB_HOME win over it
+    pub hub: Option<String>,
+    // below the hub's config.toml
+    pub reload_on_set: Option<bool>,
+    // every command is run with '<shell> -c <command>'
+    pub shell: Option<String>,
+    pub log_level: Option<LogLevel>,
+    // used by 'edit' when $VISUAL and $EDITOR aren't set
+    pub editor: Option<String>,
+    // used by 'pick', gets the entries on stdin and prints the chosen one
+    pub menu: Option<String>,
+    // entries in rofi's extended format, with icons
+    pub menu_icons: Option<bool>,
+}
+
+#[derive(Debug, Deserialize, JsonSchema, Clone, Copy, PartialEq, PartialOrd, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LogLevel {
+    Error,
+    Warn,
+    #[default]
+    Info,
+    Debug,
+}
+
+pub fn path() -> Result<PathBuf> {
+    let config_home = match env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => Path::new(&env::var("HOME").context("No $HOME set!")?).join(".config"),
+    };
+
+    Ok(config_home.join("dothub").join("config.toml"))
+}
+
+// reads the settings once, before anything uses them
+pub fn load() -> Result<()> {
+    let path = path()?;
+
+    let settings = match path.is_file() {
+        true => {
+            let contents = fs::read_to_string(&path)
+                .with_context(|| format!("Couldn't read '{}'.", path.display()))?;
+
+            let settings: Settings = schema::parse(&path, &contents)?;
+
+            Settings {
+                hub: settings.hub.as_deref().map(expand_home),
+                ..settings
+            }
+        }
+        false => Settings::default(),
+    };
+
+    let _ = SETTINGS.set(settings);
+
+    Ok(())
+}
+
+pub fn get() -> &'static Settings {
+    SETTINGS.get_or_init(Settings::default)
+}
+
+// '--hub', $DOTHUB_HOME, 'hub' of the settings, or ~/.dothub
+pub fn hub_path(hub: Option<&String>) -> Result<PathBuf> {
+    let hub = match hub {
+        Some(hub) => hub.to_owned(),
+        None => match env::var("DOTHUB_HOME") {
+            Ok(hub) if !hub.is_empty() => hub,
+            _ => match &get().hub {
+                Some(hub) => hub.to_owned(),
+                None => return default_hub_path(),
+            },
+        },
+    };
+
+    // the links point into the hub, so it has to be absolute
+    path::absolute(hub).context("Couldn't find the hub.")
+}
+
+pub fn default_hub_path() -> Result<PathBuf> {
+    Ok(Path::new(&env::var("HOME").context("No $HOME set!")?).join(".dothub"))
+}
+
+pub fn shell() -> &'static str {
+    get().shell.as_deref().unwrap_or("sh")
+}
+
+// a command of a .dothub, a profile or the schedule, ran by the shell
+pub fn shell_command(command: &str) -> process::Command {
+    debug(&format!("running '{}'", command));
+
+    let mut shell = process::Command::new(shell());
+    shell.args(["-c", command]);
+    shell
+}
+
+pub fn warn(message: &str) {
+    if get().log_level.unwrap_or_default() >= LogLevel::Warn {
+        eprintln!("{}", message);
+    }
+}
+
+pub fn debug(message: &str) {
+    if get().log_level.unwrap_or_default() >= LogLevel::Debug {
+        eprintln!("{}", message);
+    }
+}
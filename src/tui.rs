@@ -0,0 +1,447 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::{self, Child, Stdio},
+};
+
+use anyhow::{bail, Context, Result};
+use ratatui::{
+    crossterm::event::{self, Event, KeyCode, KeyEventKind},
+    layout::{Constraint, Layout},
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, List, ListItem, ListState, Paragraph, Wrap},
+    DefaultTerminal, Frame,
+};
+
+use crate::{daemon, dot_kill, dot_reload, get_active_config, layer, Hub};
+
+// the list the keys work on
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Pane {
+    DotFolders,
+    Dots,
+    Profiles,
+}
+
+struct App {
+    hub: Hub,
+    pane: Pane,
+    dotfolder: ListState,
+    dot: ListState,
+    profile: ListState,
+    // the Dot that is set, by DotFolder, looking at the links is too slow for every frame
+    active: Vec<Option<usize>>,
+    // what the last action did, and if it failed
+    message: Option<(String, bool)>,
+    use_daemon: bool,
+    // 'dothub watch' started with 'w', they stop with the TUI
+    watchers: Vec<(String, Child)>,
+}
+
+// 'dothub tui'
+pub fn tui(hub: Hub, use_daemon: bool) -> Result<()> {
+    let mut app = App {
+        hub,
+        pane: Pane::DotFolders,
+        dotfolder: ListState::default().with_selected(Some(0)),
+        dot: ListState::default(),
+        profile: ListState::default().with_selected(Some(0)),
+        active: vec![],
+        message: None,
+        use_daemon,
+        watchers: vec![],
+    };
+    app.find_active();
+    app.select_active_dot();
+
+    let mut terminal = ratatui::try_init().context("Couldn't set up the terminal.")?;
+    let result = app.run(&mut terminal);
+    ratatui::restore();
+
+    for (_, mut child) in app.watchers {
+        let _ = child.kill();
+        let _ = child.wait();
+    }
+
+    result
+}
+
+impl App {
+    fn run(&mut self, terminal: &mut DefaultTerminal) -> Result<()> {
+        loop {
+            terminal.draw(|frame| self.draw(frame))?;
+
+            let key = match event::read()? {
+                Event::Key(key) if key.kind == KeyEventKind::Press => key,
+                _ => continue,
+            };
+
+            match key.code {
+                KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+                KeyCode::Up | KeyCode::Char('k') => self.list().select_previous(),
+                KeyCode::Down | KeyCode::Char('j') => self.list().select_next(),
+                KeyCode::Tab | KeyCode::Right | KeyCode::Char('l') => self.focus(true),
+                KeyCode::BackTab | KeyCode::Left | KeyCode::Char('h') => self.focus(false),
+                KeyCode::Enter | KeyCode::Char('s') => {
+                    self.act('s');
+                    // actions print, draw everything again instead of only the changes
+                    terminal.clear()?;
+                }
+                KeyCode::Char(c @ ('w' | 'r' | 'x')) => {
+                    self.act(c);
+                    terminal.clear()?;
+                }
+                _ => {}
+            }
+
+            if self.pane == Pane::DotFolders {
+                self.select_active_dot();
+            }
+        }
+    }
+
+    fn list(&mut self) -> &mut ListState {
+        match self.pane {
+            Pane::DotFolders => &mut self.dotfolder,
+            Pane::Dots => &mut self.dot,
+            Pane::Profiles => &mut self.profile,
+        }
+    }
+
+    fn focus(&mut self, next: bool) {
+        self.pane = match (self.pane, next) {
+            (Pane::DotFolders, true) | (Pane::Profiles, false) => Pane::Dots,
+            (Pane::Dots, true) | (Pane::DotFolders, false) => Pane::Profiles,
+            (Pane::Profiles, true) | (Pane::Dots, false) => Pane::DotFolders,
+        };
+    }
+
+    fn find_active(&mut self) {
+        self.active = self
+            .hub
+            .dot_folders
+            .iter()
+            .map(|df| df.dots.iter().position(|d| self.hub.is_active(df, d)))
+            .collect();
+    }
+
+    // the index of the Dot of the selected DotFolder that is set
+    fn active_dot(&self) -> Option<usize> {
+        *self.active.get(self.dotfolder.selected()?)?
+    }
+
+    // the Dots of another DotFolder start at the one that is set
+    fn select_active_dot(&mut self) {
+        let selected = self
+            .selected_dotfolder()
+            .map(|_| self.active_dot().unwrap_or(0));
+
+        self.dot.select(selected);
+    }
+
+    fn selected_dotfolder(&self) -> Option<&crate::DotFolder> {
+        self.hub.dot_folders.get(self.dotfolder.selected()?)
+    }
+
+    // 'DotFolder/Dot' of the selected Dot
+    fn selected_location(&self) -> Option<String> {
+        let df = self.selected_dotfolder()?;
+        let d = df.dots.get(self.dot.selected()?)?;
+
+        Some(format!("{}/{}", df.name, d.name))
+    }
+
+    fn selected_profile(&self) -> Option<&crate::Profile> {
+        self.hub.profiles.get(self.profile.selected()?)
+    }
+
+    fn act(&mut self, key: char) {
+        let result = match (self.pane, key) {
+            (Pane::Profiles, 's') => match self.selected_profile() {
+                Some(profile) => {
+                    let name = profile.name.clone();
+                    self.request(&["profile-set", &name], |hub| hub.profile_set(&name))
+                        .map(|_| format!("Applied profile '{}'.", name))
+                }
+                None => return,
+            },
+            (Pane::Profiles, _) => return,
+            (_, key) => match self.selected_location() {
+                Some(location) => self.act_on_dot(key, &location),
+                None => return,
+            },
+        };
+
+        self.message = Some(match result {
+            Ok(message) => (message, false),
+            Err(e) => (format!("{:#}", e), true),
+        });
+
+        // see what changed, a broken hub keeps the old one
+        match Hub::load(&self.hub.path) {
+            Ok(hub) => self.hub = hub,
+            Err(e) => self.message = Some((format!("{:#}", e), true)),
+        }
+
+        self.find_active();
+    }
+
+    fn act_on_dot(&mut self, key: char, location: &str) -> Result<String> {
+        match key {
+            's' => self
+                .request(&["set", location], |hub| hub.set(location))
+                .map(|_| format!("Set '{}'.", location)),
+            'r' => self
+                .request(&["reload", location], |hub| {
+                    dot_reload(&get_active_config(hub.find(location)?)?)
+                })
+                .map(|_| format!("Reloaded '{}'.", location)),
+            'x' => self
+                .request(&["kill", location], |hub| {
+                    dot_kill(&get_active_config(hub.find(location)?)?)
+                })
+                .map(|_| format!("Killed '{}'.", location)),
+            'w' => self.watch(location),
+            _ => unreachable!(),
+        }
+    }
+
+    fn watch(&mut self, location: &str) -> Result<String> {
+        // the ones that stopped on their own
+        self.watchers
+            .retain_mut(|(_, child)| matches!(child.try_wait(), Ok(None)));
+
+        if let Some((_, child)) = self.watchers.iter().find(|(l, _)| l == location) {
+            bail!("'{}' is already watched, pid {}.", location, child.id());
+        }
+
+        let child = watch(&self.hub.path, location)?;
+        let pid = child.id();
+        self.watchers.push((location.to_owned(), child));
+
+        Ok(format!(
+            "Watching '{}' until the TUI is closed, pid {}.",
+            location, pid
+        ))
+    }
+
+    // like the CLI, a running daemon does it when there is one
+    fn request(&self, request: &[&str], local: impl FnOnce(&Hub) -> Result<()>) -> Result<()> {
+        daemon::send_or(&self.hub, self.use_daemon, request, local)
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [main, status] =
+            Layout::vertical([Constraint::Min(1), Constraint::Length(2)]).areas(frame.area());
+        let [left, dots, preview] = Layout::horizontal([
+            Constraint::Percentage(25),
+            Constraint::Percentage(25),
+            Constraint::Percentage(50),
+        ])
+        .areas(main);
+        let [dotfolders, profiles] =
+            Layout::vertical([Constraint::Percentage(60), Constraint::Percentage(40)]).areas(left);
+
+        let dotfolder_items: Vec<ListItem> = self
+            .hub
+            .dot_folders
+            .iter()
+            .map(|df| ListItem::new(format!("{}/", df.name)))
+            .collect();
+
+        let active = self.active_dot();
+
+        let dot_items: Vec<ListItem> = match self.selected_dotfolder() {
+            Some(df) => df
+                .dots
+                .iter()
+                .enumerate()
+                .map(|(i, d)| match active == Some(i) {
+                    true => ListItem::new(format!("{} *", d.name))
+                        .style(Style::new().fg(Color::Green).add_modifier(Modifier::BOLD)),
+                    false => ListItem::new(d.name.clone()),
+                })
+                .collect(),
+            None => vec![],
+        };
+
+        let profile_items: Vec<ListItem> = self
+            .hub
+            .profiles
+            .iter()
+            .map(|profile| ListItem::new(profile.name.clone()))
+            .collect();
+
+        let preview_text = self.preview();
+        let pane = self.pane;
+
+        let list = |items: Vec<ListItem<'static>>, title: &'static str, of: Pane| {
+            let border = match pane == of {
+                true => Style::new().fg(Color::Cyan),
+                false => Style::new(),
+            };
+
+            List::new(items)
+                .block(Block::bordered().title(title).border_style(border))
+                .highlight_style(Style::new().add_modifier(Modifier::REVERSED))
+                .highlight_symbol("> ")
+        };
+
+        frame.render_stateful_widget(
+            list(dotfolder_items, " DotFolders ", Pane::DotFolders),
+            dotfolders,
+            &mut self.dotfolder,
+        );
+        frame.render_stateful_widget(
+            list(profile_items, " Profiles ", Pane::Profiles),
+            profiles,
+            &mut self.profile,
+        );
+        frame.render_stateful_widget(list(dot_items, " Dots ", Pane::Dots), dots, &mut self.dot);
+        frame.render_widget(
+            Paragraph::new(preview_text)
+                .block(Block::bordered().title(" Preview "))
+                .wrap(Wrap { trim: false }),
+            preview,
+        );
+
+        let keys = "enter/s set  w watch  r reload  x kill  tab pane  q quit";
+        let mut lines = vec![Line::from(Span::styled(
+            keys,
+            Style::new().fg(Color::DarkGray),
+        ))];
+
+        if let Some((message, failed)) = &self.message {
+            let color = if *failed { Color::Red } else { Color::Green };
+            lines.insert(
+                0,
+                Line::from(Span::styled(message.clone(), Style::new().fg(color))),
+            );
+        }
+
+        frame.render_widget(Paragraph::new(lines), status);
+    }
+
+    fn preview(&self) -> Vec<Line<'static>> {
+        match self.pane {
+            Pane::DotFolders => match self.selected_dotfolder() {
+                Some(df) => {
+                    let path = self.hub.path.join(&df.name).join(".dothub");
+
+                    match fs::read_to_string(&path) {
+                        Ok(text) => text
+                            .lines()
+                            .map(|line| Line::from(line.to_owned()))
+                            .collect(),
+                        Err(_) => vec![Line::from("There is no .dothub.")],
+                    }
+                }
+                None => vec![],
+            },
+            Pane::Dots => self
+                .dot_preview()
+                .unwrap_or_else(|e| vec![Line::from(format!("{:#}", e))]),
+            Pane::Profiles => match self.selected_profile() {
+                Some(profile) => {
+                    let (start, dots) = profile.resolve();
+                    let mut lines = vec![Line::from("dots:")];
+
+                    let mut dots: Vec<_> = dots.unwrap_or_default().into_iter().collect();
+                    dots.sort();
+                    lines.extend(
+                        dots.into_iter()
+                            .map(|(df, d)| Line::from(format!("  {}/{}", df, d))),
+                    );
+
+                    lines.push(Line::from("start:"));
+                    lines.extend(
+                        start
+                            .unwrap_or_default()
+                            .into_iter()
+                            .map(|command| Line::from(format!("  {}", command))),
+                    );
+
+                    lines
+                }
+                None => vec![],
+            },
+        }
+    }
+
+    // the files of the Dot, compared to the Dot of the DotFolder that is set:
+    // '+' only in this one, '-' only in the set one, '~' different
+    fn dot_preview(&self) -> Result<Vec<Line<'static>>> {
+        let df = match self.selected_dotfolder() {
+            Some(df) => df,
+            None => return Ok(vec![]),
+        };
+        let d = match self.dot.selected().and_then(|i| df.dots.get(i)) {
+            Some(d) => d,
+            None => return Ok(vec![]),
+        };
+
+        let config = get_active_config((df, Some(d)))?;
+        let view = layer::dot_view(&self.hub.dot_path(df, d))?;
+        let active = self.active_dot().and_then(|i| df.dots.get(i));
+
+        let mut lines = vec![Line::from(format!("destination: {}", config.destination))];
+
+        let active_view = match active {
+            Some(other) if other.name != d.name => {
+                lines.push(Line::from(format!(
+                    "compared to '{}', which is set",
+                    other.name
+                )));
+                layer::dot_view(&self.hub.dot_path(df, other))?
+            }
+            Some(_) => {
+                lines.push(Line::from("set right now"));
+                view.clone()
+            }
+            None => {
+                lines.push(Line::from("nothing of the DotFolder is set"));
+                view.clone()
+            }
+        };
+
+        lines.push(Line::from(""));
+
+        let mut files: Vec<&PathBuf> = view.keys().chain(active_view.keys()).collect();
+        files.sort();
+        files.dedup();
+
+        for file in files {
+            let (mark, color) = match (view.get(file), active_view.get(file)) {
+                (Some(_), None) => ('+', Color::Green),
+                (None, Some(_)) => ('-', Color::Red),
+                (Some(a), Some(b)) if !same_content(a, b) => ('~', Color::Yellow),
+                _ => (' ', Color::Reset),
+            };
+
+            lines.push(Line::from(Span::styled(
+                format!("{} {}", mark, file.display()),
+                Style::new().fg(color),
+            )));
+        }
+
+        Ok(lines)
+    }
+}
+
+fn same_content(a: &Path, b: &Path) -> bool {
+    a == b || matches!((fs::read(a), fs::read(b)), (Ok(a), Ok(b)) if a == b)
+}
+
+// 'dothub watch' doesn't return, it runs next to the TUI
+fn watch(hub_path: &Path, location: &str) -> Result<Child> {
+    process::Command::new(env::current_exe().context("Couldn't find dothub.")?)
+        .arg("--hub")
+        .arg(hub_path)
+        .args(["watch", location])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .context("Couldn't start 'dothub watch'.")
+}